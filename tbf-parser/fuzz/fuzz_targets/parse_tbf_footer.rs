@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tbf_parser::parse::{parse_tbf_footer, parse_tbf_footers};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((credentials, length)) = parse_tbf_footer(data) {
+        let _ = credentials.get_type();
+        assert!(length as usize + 4 <= data.len());
+    }
+
+    for footer in parse_tbf_footers(data) {
+        let _ = footer;
+    }
+});
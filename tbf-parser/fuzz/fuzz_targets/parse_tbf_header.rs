@@ -0,0 +1,51 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tbf_parser::parse::{parse_tbf_header, parse_tbf_header_lengths, parse_tbf_header_tlvs};
+
+fuzz_target!(|data: &[u8]| {
+    // Parse the way tockloader reads apps from flash: lengths first, then the
+    // header slice.
+    let Some(lengths) = data.get(0..8) else {
+        return;
+    };
+    let version = match parse_tbf_header_lengths(lengths.try_into().unwrap()) {
+        Ok((version, header_size, _)) => match data.get(..header_size as usize) {
+            Some(_) => version,
+            None => return,
+        },
+        // Also parse headers with inconsistent lengths.
+        Err(_) => 2,
+    };
+
+    for tlv in parse_tbf_header_tlvs(data).flatten() {
+        let _ = tlv.writeable_flash_regions().count();
+    }
+
+    let Ok(header) = parse_tbf_header(data, version) else {
+        return;
+    };
+
+    let _ = header.enabled();
+    let _ = header.sticky();
+    let _ = header.get_minimum_app_ram_size();
+    let _ = header.get_app_start_offset();
+    let _ = header.get_protected_region_size();
+    let _ = header.get_init_function_offset();
+    let _ = header.get_package_name();
+    for i in 0..=header.number_writeable_flash_regions() {
+        let _ = header.get_writeable_flash_region(i);
+    }
+    let _ = header.get_fixed_address_ram();
+    let _ = header.get_fixed_address_flash();
+    let _ = header.get_command_permissions(1, 0);
+    let _ = header.get_storage_read_ids();
+    let _ = header.get_storage_modify_ids();
+    let _ = header.get_kernel_version();
+    let _ = header.get_fixed_short_id();
+
+    // Callers compute the size of the footers from these.
+    if header.is_app() {
+        assert!(header.get_binary_end() <= header.total_size());
+    }
+});
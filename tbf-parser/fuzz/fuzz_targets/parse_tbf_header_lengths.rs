@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tbf_parser::parse::parse_tbf_header_lengths;
+
+fuzz_target!(|data: [u8; 8]| {
+    if let Ok((_, header_size, total_size)) = parse_tbf_header_lengths(&data) {
+        assert!(header_size >= 16);
+        assert!(u32::from(header_size) <= total_size);
+    }
+});
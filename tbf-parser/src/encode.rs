@@ -0,0 +1,693 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+//! Tock Binary Format encoding code.
+//!
+//! This is the counterpart of [`parse`](crate::parse): every type that can be
+//! parsed out of a TBF header can be turned back into bytes through
+//! [`TbfEncode`]. Encoding never allocates, the caller provides the output
+//! buffer and can size it using [`TbfEncode::encoded_len`].
+//!
+//! TLV entries are padded to 4 bytes, and whole headers are written with the
+//! `header_size` and `checksum` fields computed from their contents, so that
+//! `parse_tbf_header` accepts the result.
+//!
+//! DELTA: Originally did not exist
+
+use core::num::NonZeroU32;
+
+use crate::types::{
+    TbfEncodeError, TbfHeader, TbfHeaderDriverPermission, TbfHeaderTypes, TbfHeaderV2,
+    TbfHeaderV2Base, TbfHeaderV2FixedAddresses, TbfHeaderV2KernelVersion, TbfHeaderV2Main,
+    TbfHeaderV2PackageName, TbfHeaderV2Permissions, TbfHeaderV2Program, TbfHeaderV2ShortId,
+    TbfHeaderV2StoragePermissions, TbfHeaderV2WriteableFlashRegion,
+};
+
+/// Size of the fields present at the start of every v2 header.
+const BASE_SIZE: usize = 16;
+
+/// Index of the checksum word in the header, which is skipped when computing
+/// the checksum.
+const CHECKSUM_WORD: usize = 3;
+
+/// Maximum number of writeable flash regions a [`TbfHeaderV2`] can hold.
+const MAX_WRITEABLE_FLASH_REGIONS: usize = 4;
+
+/// Destination of encoded bytes.
+///
+/// An encoder either writes into a buffer, or only keeps track of the number
+/// of bytes and the checksum of what would have been written.
+pub struct Encoder<'a> {
+    buffer: Option<&'a mut [u8]>,
+    position: usize,
+    checksum: u32,
+}
+
+impl<'a> Encoder<'a> {
+    fn new(buffer: &'a mut [u8]) -> Self {
+        Encoder {
+            buffer: Some(buffer),
+            position: 0,
+            checksum: 0,
+        }
+    }
+
+    fn sizing() -> Encoder<'static> {
+        Encoder {
+            buffer: None,
+            position: 0,
+            checksum: 0,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), TbfEncodeError> {
+        for &byte in bytes {
+            if let Some(buffer) = self.buffer.as_deref_mut() {
+                *buffer
+                    .get_mut(self.position)
+                    .ok_or(TbfEncodeError::BufferTooSmall(self.position + 1))? = byte;
+            }
+
+            // The checksum is the XOR of each little-endian 4 byte word in
+            // the header, which is the same as XOR-ing each byte shifted to
+            // its place in the word.
+            if self.position / 4 != CHECKSUM_WORD {
+                self.checksum ^= u32::from(byte) << (8 * (self.position % 4));
+            }
+            self.position += 1;
+        }
+        Ok(())
+    }
+
+    fn write_u16(&mut self, value: u16) -> Result<(), TbfEncodeError> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, value: u32) -> Result<(), TbfEncodeError> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, value: u64) -> Result<(), TbfEncodeError> {
+        self.write(&value.to_le_bytes())
+    }
+
+    /// Write a complete TLV entry. `length` is the length of the value, which
+    /// is written by `value` and then padded to 4 bytes.
+    fn write_tlv(
+        &mut self,
+        tipe: TbfHeaderTypes,
+        length: usize,
+        value: impl FnOnce(&mut Self) -> Result<(), TbfEncodeError>,
+    ) -> Result<(), TbfEncodeError> {
+        let encoded_length =
+            u16::try_from(length).map_err(|_| TbfEncodeError::HeaderTooLarge(length))?;
+
+        self.write_u16(tipe as u16)?;
+        self.write_u16(encoded_length)?;
+        value(self)?;
+
+        // All TLV blocks are padded to 4 bytes.
+        while !self.position.is_multiple_of(4) {
+            self.write(&[0])?;
+        }
+        Ok(())
+    }
+}
+
+/// Types that can be encoded into the bytes of a TBF header.
+pub trait TbfEncode {
+    /// Write the encoded representation of `self` into `encoder`.
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError>;
+
+    /// Number of bytes written by [`encode`](TbfEncode::encode).
+    fn encoded_len(&self) -> Result<usize, TbfEncodeError> {
+        let mut encoder = Encoder::sizing();
+        self.encode_into(&mut encoder)?;
+        Ok(encoder.position)
+    }
+
+    /// Encode `self` at the start of `buffer`.
+    ///
+    /// ## Return
+    ///
+    /// The number of bytes written, or `BufferTooSmall` if `buffer` is shorter
+    /// than [`encoded_len`](TbfEncode::encoded_len).
+    fn encode(&self, buffer: &mut [u8]) -> Result<usize, TbfEncodeError> {
+        let length = self.encoded_len()?;
+        if buffer.len() < length {
+            return Err(TbfEncodeError::BufferTooSmall(length));
+        }
+
+        let mut encoder = Encoder::new(buffer);
+        self.encode_into(&mut encoder)?;
+        Ok(encoder.position)
+    }
+}
+
+// Constructors for the header types. Parsing is the only other way to create
+// them, so these are needed to build headers from scratch.
+
+impl TbfHeaderV2Base {
+    /// Create the base of a padding TBF object that spans `total_size` bytes.
+    ///
+    /// A padding object is a header with no TLVs, used to fill the space
+    /// between two apps. The `checksum` is computed automatically.
+    pub fn new_padding(total_size: u32) -> Result<Self, TbfEncodeError> {
+        if total_size < BASE_SIZE as u32 {
+            return Err(TbfEncodeError::TotalSizeTooSmall(total_size));
+        }
+
+        let mut base = TbfHeaderV2Base {
+            version: 2,
+            header_size: BASE_SIZE as u16,
+            total_size,
+            flags: 0,
+            checksum: 0,
+        };
+
+        let mut encoder = Encoder::sizing();
+        base.encode_into(&mut encoder)?;
+        base.checksum = encoder.checksum;
+
+        Ok(base)
+    }
+}
+
+impl TbfHeaderV2Main {
+    pub fn new(init_fn_offset: u32, protected_trailer_size: u32, minimum_ram_size: u32) -> Self {
+        TbfHeaderV2Main {
+            init_fn_offset,
+            protected_trailer_size,
+            minimum_ram_size,
+        }
+    }
+}
+
+impl TbfHeaderV2Program {
+    pub fn new(
+        init_fn_offset: u32,
+        protected_trailer_size: u32,
+        minimum_ram_size: u32,
+        binary_end_offset: u32,
+        version: u32,
+    ) -> Self {
+        TbfHeaderV2Program {
+            init_fn_offset,
+            protected_trailer_size,
+            minimum_ram_size,
+            binary_end_offset,
+            version,
+        }
+    }
+}
+
+impl<const L: usize> TbfHeaderV2PackageName<L> {
+    pub fn new(name: &str) -> Result<Self, TbfEncodeError> {
+        if name.len() > L {
+            return Err(TbfEncodeError::PackageNameTooLong);
+        }
+
+        let mut buffer = [0u8; L];
+        buffer[..name.len()].copy_from_slice(name.as_bytes());
+
+        Ok(TbfHeaderV2PackageName {
+            size: name.len() as u32,
+            buffer,
+        })
+    }
+}
+
+impl TbfHeaderV2WriteableFlashRegion {
+    pub fn new(offset: u32, size: u32) -> Self {
+        TbfHeaderV2WriteableFlashRegion {
+            writeable_flash_region_offset: offset,
+            writeable_flash_region_size: size,
+        }
+    }
+}
+
+impl TbfHeaderV2FixedAddresses {
+    /// Use `0xFFFFFFFF` for an address that should not be fixed.
+    pub fn new(start_process_ram: u32, start_process_flash: u32) -> Self {
+        TbfHeaderV2FixedAddresses {
+            start_process_ram,
+            start_process_flash,
+        }
+    }
+}
+
+impl TbfHeaderDriverPermission {
+    pub fn new(driver_number: u32, offset: u32, allowed_commands: u64) -> Self {
+        TbfHeaderDriverPermission {
+            driver_number,
+            offset,
+            allowed_commands,
+        }
+    }
+}
+
+impl<const L: usize> TbfHeaderV2Permissions<L> {
+    pub fn new(permissions: &[TbfHeaderDriverPermission]) -> Result<Self, TbfEncodeError> {
+        if permissions.len() > L {
+            return Err(TbfEncodeError::TooManyEntries(
+                TbfHeaderTypes::TbfHeaderPermissions as usize,
+            ));
+        }
+
+        let mut perms = [TbfHeaderDriverPermission::default(); L];
+        perms[..permissions.len()].copy_from_slice(permissions);
+
+        Ok(TbfHeaderV2Permissions {
+            length: permissions.len() as u16,
+            perms,
+        })
+    }
+}
+
+impl<const L: usize> TbfHeaderV2StoragePermissions<L> {
+    pub fn new(
+        write_id: Option<NonZeroU32>,
+        read_ids: &[u32],
+        modify_ids: &[u32],
+    ) -> Result<Self, TbfEncodeError> {
+        if read_ids.len() > L || modify_ids.len() > L {
+            return Err(TbfEncodeError::TooManyEntries(
+                TbfHeaderTypes::TbfHeaderStoragePermissions as usize,
+            ));
+        }
+
+        let mut permissions = TbfHeaderV2StoragePermissions {
+            write_id,
+            read_length: read_ids.len() as u16,
+            read_ids: [0; L],
+            modify_length: modify_ids.len() as u16,
+            modify_ids: [0; L],
+        };
+        permissions.read_ids[..read_ids.len()].copy_from_slice(read_ids);
+        permissions.modify_ids[..modify_ids.len()].copy_from_slice(modify_ids);
+
+        Ok(permissions)
+    }
+}
+
+impl TbfHeaderV2KernelVersion {
+    pub fn new(major: u16, minor: u16) -> Self {
+        TbfHeaderV2KernelVersion { major, minor }
+    }
+}
+
+impl TbfHeaderV2ShortId {
+    pub fn new(short_id: Option<NonZeroU32>) -> Self {
+        TbfHeaderV2ShortId { short_id }
+    }
+}
+
+// Building and editing full headers.
+//
+// Every method that changes a `TbfHeaderV2` also updates its `header_size` and
+// `checksum`, so that a header always matches what `parse_tbf_header` returns
+// for its encoded bytes.
+
+impl TbfHeaderV2 {
+    /// Create a header with no TLV entries for a TBF object of `total_size`
+    /// bytes.
+    ///
+    /// Until a Main or Program TLV is added, the encoded header describes
+    /// padding rather than an app.
+    pub fn new(total_size: u32, flags: u32) -> Self {
+        let mut header = TbfHeaderV2 {
+            base: TbfHeaderV2Base {
+                version: 2,
+                header_size: BASE_SIZE as u16,
+                total_size,
+                flags,
+                checksum: 0,
+            },
+            main: None,
+            program: None,
+            package_name: None,
+            writeable_regions: Some([None; MAX_WRITEABLE_FLASH_REGIONS]),
+            fixed_addresses: None,
+            permissions: None,
+            storage_permissions: None,
+            kernel_version: None,
+            short_id: None,
+        };
+        header.update_base();
+        header
+    }
+
+    pub fn set_total_size(&mut self, total_size: u32) {
+        self.base.total_size = total_size;
+        self.update_base();
+    }
+
+    pub fn set_flags(&mut self, flags: u32) {
+        self.base.flags = flags;
+        self.update_base();
+    }
+
+    /// Set whether the kernel should start the application.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_flag(0x00000001, enabled);
+    }
+
+    /// Set whether the application requires additional confirmation to be
+    /// erased.
+    pub fn set_sticky(&mut self, sticky: bool) {
+        self.set_flag(0x00000002, sticky);
+    }
+
+    fn set_flag(&mut self, mask: u32, value: bool) {
+        if value {
+            self.set_flags(self.base.flags | mask);
+        } else {
+            self.set_flags(self.base.flags & !mask);
+        }
+    }
+
+    pub fn set_main(&mut self, main: Option<TbfHeaderV2Main>) {
+        self.main = main;
+        self.update_base();
+    }
+
+    pub fn set_program(&mut self, program: Option<TbfHeaderV2Program>) {
+        self.program = program;
+        self.update_base();
+    }
+
+    pub fn set_package_name(&mut self, name: Option<&str>) -> Result<(), TbfEncodeError> {
+        self.package_name = name.map(TbfHeaderV2PackageName::new).transpose()?;
+        self.update_base();
+        Ok(())
+    }
+
+    /// Replace all writeable flash regions. At most four regions are
+    /// supported.
+    pub fn set_writeable_flash_regions(
+        &mut self,
+        regions: &[TbfHeaderV2WriteableFlashRegion],
+    ) -> Result<(), TbfEncodeError> {
+        if regions.len() > MAX_WRITEABLE_FLASH_REGIONS {
+            return Err(TbfEncodeError::TooManyEntries(
+                TbfHeaderTypes::TbfHeaderWriteableFlashRegions as usize,
+            ));
+        }
+
+        let mut writeable_regions = [None; MAX_WRITEABLE_FLASH_REGIONS];
+        for (slot, region) in writeable_regions.iter_mut().zip(regions) {
+            *slot = Some(*region);
+        }
+        self.writeable_regions = Some(writeable_regions);
+        self.update_base();
+        Ok(())
+    }
+
+    pub fn set_fixed_addresses(&mut self, fixed_addresses: Option<TbfHeaderV2FixedAddresses>) {
+        self.fixed_addresses = fixed_addresses;
+        self.update_base();
+    }
+
+    pub fn set_permissions(&mut self, permissions: Option<TbfHeaderV2Permissions<8>>) {
+        self.permissions = permissions;
+        self.update_base();
+    }
+
+    pub fn set_storage_permissions(
+        &mut self,
+        storage_permissions: Option<TbfHeaderV2StoragePermissions<8>>,
+    ) {
+        self.storage_permissions = storage_permissions;
+        self.update_base();
+    }
+
+    pub fn set_kernel_version(&mut self, kernel_version: Option<TbfHeaderV2KernelVersion>) {
+        self.kernel_version = kernel_version;
+        self.update_base();
+    }
+
+    pub fn set_short_id(&mut self, short_id: Option<TbfHeaderV2ShortId>) {
+        self.short_id = short_id;
+        self.update_base();
+    }
+
+    /// Recompute `header_size` and `checksum` from the rest of the header.
+    ///
+    /// If the header is too large to be encoded, the previous values are
+    /// kept and the error is reported when encoding.
+    fn update_base(&mut self) {
+        if let Ok((header_size, checksum)) = self.compute_size_and_checksum() {
+            self.base.header_size = header_size;
+            self.base.checksum = checksum;
+        }
+    }
+
+    fn compute_size_and_checksum(&self) -> Result<(u16, u32), TbfEncodeError> {
+        let mut encoder = Encoder::sizing();
+        self.encode_tlvs(&mut encoder)?;
+        let length = BASE_SIZE + encoder.position;
+        let header_size =
+            u16::try_from(length).map_err(|_| TbfEncodeError::HeaderTooLarge(length))?;
+
+        // The header size is part of the checksummed words, so the checksum
+        // can only be computed once the size is known.
+        let mut encoder = Encoder::sizing();
+        encode_base(&mut encoder, &self.base, header_size, 0)?;
+        self.encode_tlvs(&mut encoder)?;
+
+        Ok((header_size, encoder.checksum))
+    }
+
+    fn encode_tlvs(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        if let Some(main) = &self.main {
+            main.encode_into(encoder)?;
+        }
+        if let Some(program) = &self.program {
+            program.encode_into(encoder)?;
+        }
+        if let Some(package_name) = &self.package_name {
+            package_name.encode_into(encoder)?;
+        }
+        if let Some(regions) = &self.writeable_regions {
+            let mut buffer =
+                [TbfHeaderV2WriteableFlashRegion::default(); MAX_WRITEABLE_FLASH_REGIONS];
+            let mut count = 0;
+            for region in regions.iter().flatten() {
+                buffer[count] = *region;
+                count += 1;
+            }
+            if count > 0 {
+                buffer[..count].encode_into(encoder)?;
+            }
+        }
+        if let Some(fixed_addresses) = &self.fixed_addresses {
+            fixed_addresses.encode_into(encoder)?;
+        }
+        if let Some(permissions) = &self.permissions {
+            permissions.encode_into(encoder)?;
+        }
+        if let Some(storage_permissions) = &self.storage_permissions {
+            storage_permissions.encode_into(encoder)?;
+        }
+        if let Some(kernel_version) = &self.kernel_version {
+            kernel_version.encode_into(encoder)?;
+        }
+        if let Some(short_id) = &self.short_id {
+            short_id.encode_into(encoder)?;
+        }
+        Ok(())
+    }
+}
+
+impl TbfHeader {
+    /// Create a padding TBF object that spans `total_size` bytes.
+    pub fn new_padding(total_size: u32) -> Result<Self, TbfEncodeError> {
+        Ok(TbfHeader::Padding(TbfHeaderV2Base::new_padding(
+            total_size,
+        )?))
+    }
+}
+
+fn encode_base(
+    encoder: &mut Encoder<'_>,
+    base: &TbfHeaderV2Base,
+    header_size: u16,
+    checksum: u32,
+) -> Result<(), TbfEncodeError> {
+    encoder.write_u16(base.version)?;
+    encoder.write_u16(header_size)?;
+    encoder.write_u32(base.total_size)?;
+    encoder.write_u32(base.flags)?;
+    encoder.write_u32(checksum)
+}
+
+// Encoders
+
+impl TbfEncode for TbfHeaderV2Base {
+    /// The base is written exactly as stored, including `header_size` and
+    /// `checksum`. Encode a [`TbfHeaderV2`] or [`TbfHeader`] to have them
+    /// computed.
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        encode_base(encoder, self, self.header_size, self.checksum)
+    }
+}
+
+impl TbfEncode for TbfHeaderV2Main {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        encoder.write_tlv(TbfHeaderTypes::TbfHeaderMain, 12, |encoder| {
+            encoder.write_u32(self.init_fn_offset)?;
+            encoder.write_u32(self.protected_trailer_size)?;
+            encoder.write_u32(self.minimum_ram_size)
+        })
+    }
+}
+
+impl TbfEncode for TbfHeaderV2Program {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        encoder.write_tlv(TbfHeaderTypes::TbfHeaderProgram, 20, |encoder| {
+            encoder.write_u32(self.init_fn_offset)?;
+            encoder.write_u32(self.protected_trailer_size)?;
+            encoder.write_u32(self.minimum_ram_size)?;
+            encoder.write_u32(self.binary_end_offset)?;
+            encoder.write_u32(self.version)
+        })
+    }
+}
+
+impl<const L: usize> TbfEncode for TbfHeaderV2PackageName<L> {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        let name = &self.buffer[..self.size as usize];
+        encoder.write_tlv(
+            TbfHeaderTypes::TbfHeaderPackageName,
+            name.len(),
+            |encoder| encoder.write(name),
+        )
+    }
+}
+
+/// All writeable flash regions are stored in a single TLV entry.
+impl TbfEncode for [TbfHeaderV2WriteableFlashRegion] {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        encoder.write_tlv(
+            TbfHeaderTypes::TbfHeaderWriteableFlashRegions,
+            self.len() * 8,
+            |encoder| {
+                for region in self {
+                    encoder.write_u32(region.writeable_flash_region_offset)?;
+                    encoder.write_u32(region.writeable_flash_region_size)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl TbfEncode for TbfHeaderV2FixedAddresses {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        encoder.write_tlv(TbfHeaderTypes::TbfHeaderFixedAddresses, 8, |encoder| {
+            encoder.write_u32(self.start_process_ram)?;
+            encoder.write_u32(self.start_process_flash)
+        })
+    }
+}
+
+impl<const L: usize> TbfEncode for TbfHeaderV2Permissions<L> {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        let perms =
+            self.perms
+                .get(..self.length as usize)
+                .ok_or(TbfEncodeError::TooManyEntries(
+                    TbfHeaderTypes::TbfHeaderPermissions as usize,
+                ))?;
+
+        encoder.write_tlv(
+            TbfHeaderTypes::TbfHeaderPermissions,
+            2 + perms.len() * 16,
+            |encoder| {
+                encoder.write_u16(self.length)?;
+                for perm in perms {
+                    encoder.write_u32(perm.driver_number)?;
+                    encoder.write_u32(perm.offset)?;
+                    encoder.write_u64(perm.allowed_commands)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<const L: usize> TbfEncode for TbfHeaderV2StoragePermissions<L> {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        let too_many =
+            || TbfEncodeError::TooManyEntries(TbfHeaderTypes::TbfHeaderStoragePermissions as usize);
+        let read_ids = self
+            .read_ids
+            .get(..self.read_length as usize)
+            .ok_or_else(too_many)?;
+        let modify_ids = self
+            .modify_ids
+            .get(..self.modify_length as usize)
+            .ok_or_else(too_many)?;
+
+        encoder.write_tlv(
+            TbfHeaderTypes::TbfHeaderStoragePermissions,
+            4 + 2 + read_ids.len() * 4 + 2 + modify_ids.len() * 4,
+            |encoder| {
+                encoder.write_u32(self.write_id.map_or(0, NonZeroU32::get))?;
+                encoder.write_u16(self.read_length)?;
+                for id in read_ids {
+                    encoder.write_u32(*id)?;
+                }
+                encoder.write_u16(self.modify_length)?;
+                for id in modify_ids {
+                    encoder.write_u32(*id)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl TbfEncode for TbfHeaderV2KernelVersion {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        encoder.write_tlv(TbfHeaderTypes::TbfHeaderKernelVersion, 4, |encoder| {
+            encoder.write_u16(self.major)?;
+            encoder.write_u16(self.minor)
+        })
+    }
+}
+
+impl TbfEncode for TbfHeaderV2ShortId {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        encoder.write_tlv(TbfHeaderTypes::TbfHeaderShortId, 4, |encoder| {
+            encoder.write_u32(self.short_id.map_or(0, NonZeroU32::get))
+        })
+    }
+}
+
+/// Encodes the whole header. `header_size` and `checksum` are computed from
+/// the TLV entries rather than taken from the stored base.
+impl TbfEncode for TbfHeaderV2 {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        let (header_size, checksum) = self.compute_size_and_checksum()?;
+        if self.base.total_size < u32::from(header_size) {
+            return Err(TbfEncodeError::TotalSizeTooSmall(self.base.total_size));
+        }
+
+        encode_base(encoder, &self.base, header_size, checksum)?;
+        self.encode_tlvs(encoder)
+    }
+}
+
+impl TbfEncode for TbfHeader {
+    fn encode_into(&self, encoder: &mut Encoder<'_>) -> Result<(), TbfEncodeError> {
+        match self {
+            TbfHeader::TbfHeaderV2(header) => header.encode_into(encoder),
+            TbfHeader::Padding(base) => {
+                let padding = TbfHeaderV2Base::new_padding(base.total_size)?;
+                encode_base(encoder, &padding, padding.header_size, padding.checksum)
+            }
+        }
+    }
+}
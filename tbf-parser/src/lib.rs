@@ -11,6 +11,12 @@
 #![forbid(unsafe_code)]
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod encode;
 pub mod parse;
 #[allow(dead_code)] // Some fields not read on device, but read when creating headers
 pub mod types;
@@ -147,12 +147,22 @@ pub fn parse_tbf_header(
 
                 // Iterate the remainder of the header looking for TLV entries.
                 while !remaining.is_empty() {
+                    let offset = header.len() - remaining.len();
+
                     // Get the T and L portions of the next header (if it is
                     // there).
-                    let tlv_header: types::TbfTlv = remaining
+                    let tlv_bytes = remaining
                         .get(0..4)
-                        .ok_or(types::TbfParseError::NotEnoughFlash)?
-                        .try_into()?;
+                        .ok_or(types::TbfParseError::NotEnoughFlash)?;
+                    let tlv_header: types::TbfTlv = tlv_bytes.try_into()?;
+                    let tipe = u16::from_le_bytes([tlv_bytes[0], tlv_bytes[1]]);
+                    let length_mismatch =
+                        |expected: usize| types::TbfParseError::TlvLengthMismatch {
+                            offset,
+                            tipe,
+                            expected,
+                            actual: tlv_header.length as usize,
+                        };
 
                     log::debug!(
                         "TLV Entry: type {:?}, length {}",
@@ -164,6 +174,16 @@ pub fn parse_tbf_header(
                         .get(4..)
                         .ok_or(types::TbfParseError::NotEnoughFlash)?;
 
+                    // DELTA: Originally only checked by each entry type
+                    if remaining.len() < tlv_header.length as usize {
+                        return Err(types::TbfParseError::TlvOutOfBounds {
+                            offset,
+                            tipe,
+                            length: tlv_header.length as usize,
+                            available: remaining.len(),
+                        });
+                    }
+
                     match tlv_header.tipe {
                         types::TbfHeaderTypes::TbfHeaderMain => {
                             let entry_len = mem::size_of::<types::TbfHeaderV2Main>();
@@ -178,9 +198,7 @@ pub fn parse_tbf_header(
                                             .try_into()?,
                                     );
                                 } else {
-                                    return Err(types::TbfParseError::BadTlvEntry(
-                                        tlv_header.tipe as usize,
-                                    ));
+                                    return Err(length_mismatch(entry_len));
                                 }
                             }
                         }
@@ -195,9 +213,7 @@ pub fn parse_tbf_header(
                                             .try_into()?,
                                     );
                                 } else {
-                                    return Err(types::TbfParseError::BadTlvEntry(
-                                        tlv_header.tipe as usize,
-                                    ));
+                                    return Err(length_mismatch(entry_len));
                                 }
                             }
                         }
@@ -261,9 +277,7 @@ pub fn parse_tbf_header(
                                         .try_into()?,
                                 );
                             } else {
-                                return Err(types::TbfParseError::BadTlvEntry(
-                                    tlv_header.tipe as usize,
-                                ));
+                                return Err(length_mismatch(entry_len));
                             }
                         }
 
@@ -285,9 +299,7 @@ pub fn parse_tbf_header(
                                         .try_into()?,
                                 );
                             } else {
-                                return Err(types::TbfParseError::BadTlvEntry(
-                                    tlv_header.tipe as usize,
-                                ));
+                                return Err(length_mismatch(entry_len));
                             }
                         }
 
@@ -301,9 +313,7 @@ pub fn parse_tbf_header(
                                         .try_into()?,
                                 );
                             } else {
-                                return Err(types::TbfParseError::BadTlvEntry(
-                                    tlv_header.tipe as usize,
-                                ));
+                                return Err(length_mismatch(entry_len));
                             }
                         }
 
@@ -318,6 +328,30 @@ pub fn parse_tbf_header(
                         .ok_or(types::TbfParseError::NotEnoughFlash)?;
                 }
 
+                // Values used for arithmetic by the accessors and by callers
+                // must stay within the TBF object.
+                //
+                // DELTA: Originally did not exist
+                let total_size = tbf_header_base.total_size;
+                let header_size = u32::from(tbf_header_base.header_size);
+                let fits = |protected_trailer_size: u32| {
+                    header_size
+                        .checked_add(protected_trailer_size)
+                        .is_some_and(|protected_size| protected_size <= total_size)
+                };
+                if main_pointer.is_some_and(|main| !fits(main.protected_trailer_size)) {
+                    return Err(types::TbfParseError::BadTlvEntry(
+                        types::TbfHeaderTypes::TbfHeaderMain as usize,
+                    ));
+                }
+                if program_pointer.is_some_and(|program| {
+                    !fits(program.protected_trailer_size) || program.binary_end_offset > total_size
+                }) {
+                    return Err(types::TbfParseError::BadTlvEntry(
+                        types::TbfHeaderTypes::TbfHeaderProgram as usize,
+                    ));
+                }
+
                 let tbf_header = types::TbfHeaderV2 {
                     base: tbf_header_base,
                     main: main_pointer,
@@ -350,7 +384,7 @@ pub fn parse_tbf_footer(
         types::TbfHeaderTypes::TbfFooterCredentials => {
             let credential: types::TbfFooterV2Credentials = remaining
                 .get(0..tlv_header.length as usize)
-                .ok_or(types::TbfParseError::NotEnoughFlash)?
+                .ok_or_else(|| tlv_out_of_bounds(0, footers))?
                 .try_into()?;
             // Check length here
             let length = tlv_header.length;
@@ -359,3 +393,155 @@ pub fn parse_tbf_footer(
         _ => Err(types::TbfParseError::BadTlvEntry(tlv_header.tipe as usize)),
     }
 }
+
+/// Error for the TLV entry at the start of `remaining`, at `offset`, whose
+/// value does not fit in `remaining`.
+fn tlv_out_of_bounds(offset: usize, remaining: &[u8]) -> types::TbfParseError {
+    types::TbfParseError::TlvOutOfBounds {
+        offset,
+        tipe: u16::from_le_bytes([remaining[0], remaining[1]]),
+        length: u16::from_le_bytes([remaining[2], remaining[3]]) as usize,
+        available: remaining.len() - 4,
+    }
+}
+
+/// Iterate over every TLV entry in the footers of a TBF.
+///
+/// `footers` must span from the end of the binary (`binary_end_offset`) to the
+/// end of the TBF object (`total_size`). Credentials footers are parsed,
+/// footers of unknown types are returned as raw bytes, and trailing bytes that
+/// cannot hold a TLV are reported as padding.
+///
+/// A credentials footer that cannot be parsed is returned as an error, and
+/// iteration continues with the next footer. A footer whose length runs past
+/// the end of `footers` is also returned as an error, and ends the iteration.
+///
+/// DELTA: Originally did not exist
+pub fn parse_tbf_footers(footers: &[u8]) -> TbfFooterIterator<'_> {
+    TbfFooterIterator { footers, offset: 0 }
+}
+
+/// Iterator returned by [`parse_tbf_footers`].
+///
+/// DELTA: Originally did not exist
+#[derive(Clone, Debug)]
+pub struct TbfFooterIterator<'a> {
+    footers: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for TbfFooterIterator<'a> {
+    type Item = Result<types::TbfFooterTlv<'a>, types::TbfParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let remaining = self.footers.get(offset..).filter(|r| !r.is_empty())?;
+
+        let tlv_header: types::TbfTlv = match remaining.try_into() {
+            Ok(tlv_header) => tlv_header,
+            Err(_) => {
+                self.offset = self.footers.len();
+                return Some(Ok(types::TbfFooterTlv::Padding {
+                    offset,
+                    length: remaining.len(),
+                }));
+            }
+        };
+
+        let data = match remaining.get(4..4 + tlv_header.length as usize) {
+            Some(data) => data,
+            None => {
+                self.offset = self.footers.len();
+                return Some(Err(tlv_out_of_bounds(offset, remaining)));
+            }
+        };
+        self.offset += 4 + data.len();
+
+        match tlv_header.tipe {
+            types::TbfHeaderTypes::TbfFooterCredentials => Some(data.try_into().map(
+                |credentials| types::TbfFooterTlv::Credentials {
+                    offset,
+                    length: tlv_header.length,
+                    credentials,
+                },
+            )),
+            _ => Some(Ok(types::TbfFooterTlv::Unknown {
+                offset,
+                tipe: u16::from_le_bytes([remaining[0], remaining[1]]),
+                data,
+            })),
+        }
+    }
+}
+
+/// Iterate over every TLV entry in a TBF header.
+///
+/// `header` must contain the whole header, starting with the base fields, as
+/// passed to [`parse_tbf_header`]. Entries are returned in header order with
+/// their raw type and value, whether this parser knows their type or not.
+///
+/// An entry that runs past the end of `header` is returned as an error, and
+/// ends the iteration.
+///
+/// DELTA: Originally did not exist
+pub fn parse_tbf_header_tlvs(header: &[u8]) -> TbfHeaderTlvIterator<'_> {
+    TbfHeaderTlvIterator { header, offset: 16 }
+}
+
+/// Iterator returned by [`parse_tbf_header_tlvs`].
+///
+/// DELTA: Originally did not exist
+#[derive(Clone, Debug)]
+pub struct TbfHeaderTlvIterator<'a> {
+    header: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for TbfHeaderTlvIterator<'a> {
+    type Item = Result<types::TbfHeaderTlv<'a>, types::TbfParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let remaining = match self.header.get(offset..) {
+            Some(remaining) if !remaining.is_empty() => remaining,
+            Some(_) => return None,
+            // The header is shorter than its base fields.
+            None if offset == 16 => {
+                self.offset = usize::MAX;
+                return Some(Err(types::TbfParseError::NotEnoughFlash));
+            }
+            None => return None,
+        };
+
+        let Some(tlv) = remaining.get(0..4) else {
+            self.offset = usize::MAX;
+            return Some(Err(types::TbfParseError::NotEnoughFlash));
+        };
+        let length = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+        let Some(data) = remaining.get(4..4 + length) else {
+            self.offset = usize::MAX;
+            return Some(Err(tlv_out_of_bounds(offset, remaining)));
+        };
+
+        // All TLV blocks are padded to 4 bytes.
+        self.offset += 4 + align4!(data.len());
+
+        Some(Ok(types::TbfHeaderTlv {
+            tipe: u16::from_le_bytes([remaining[0], remaining[1]]),
+            offset,
+            data,
+        }))
+    }
+}
+
+/// Copy every TLV entry of a TBF header. See [`parse_tbf_header_tlvs`].
+///
+/// DELTA: Originally did not exist
+#[cfg(feature = "alloc")]
+pub fn parse_tbf_header_raw(
+    header: &[u8],
+) -> Result<alloc::vec::Vec<types::TbfRawTlv>, types::TbfParseError> {
+    parse_tbf_header_tlvs(header)
+        .map(|tlv| tlv.map(types::TbfRawTlv::from))
+        .collect()
+}
@@ -11,12 +11,16 @@ use core::convert::TryInto;
 use core::mem::size_of;
 use core::{fmt, str};
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 /// We only support up to a fixed number of storage permissions for each of read
 /// and modify. This simplification enables us to use fixed sized buffers.
 const NUM_STORAGE_PERMISSIONS: usize = 8;
 
 /// Error when parsing just the beginning of the TBF header. This is only used
 /// when establishing the linked list structure of apps installed in flash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InitialTbfParseError {
     /// We were unable to parse the beginning of the header. This either means
     /// we ran out of flash, or the trusted values are invalid meaning this is
@@ -40,7 +44,22 @@ impl From<core::array::TryFromSliceError> for InitialTbfParseError {
     }
 }
 
+impl fmt::Display for InitialTbfParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InitialTbfParseError::UnableToParse => write!(f, "No TBF header found"),
+            InitialTbfParseError::InvalidHeader(total_size) => {
+                write!(f, "Invalid TBF header lengths, total size {total_size}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InitialTbfParseError {}
+
 /// Error when parsing an app's TBF header.
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum TbfParseError {
     /// Not enough bytes in the buffer to parse the expected field.
     NotEnoughFlash,
@@ -58,6 +77,37 @@ pub enum TbfParseError {
     /// is the value of the "tipe" field.
     BadTlvEntry(usize),
 
+    /// The length of a fixed-size TLV entry does not match the size of its
+    /// type.
+    ///
+    /// DELTA: Originally reported as `BadTlvEntry`
+    TlvLengthMismatch {
+        /// Offset of the entry from the start of the header.
+        offset: usize,
+        /// Raw type of the entry.
+        tipe: u16,
+        /// Length of the entry type.
+        expected: usize,
+        /// Length stored in the entry.
+        actual: usize,
+    },
+
+    /// The value of a TLV entry runs past the end of the header, or of the
+    /// footers.
+    ///
+    /// DELTA: Originally reported as `NotEnoughFlash`
+    TlvOutOfBounds {
+        /// Offset of the entry from the start of the header, or of the
+        /// footers.
+        offset: usize,
+        /// Raw type of the entry.
+        tipe: u16,
+        /// Length stored in the entry.
+        length: usize,
+        /// Number of bytes left after the type and length of the entry.
+        available: usize,
+    },
+
     /// The app name in the TBF header could not be successfully parsed as a
     /// UTF-8 string.
     BadProcessName,
@@ -89,7 +139,7 @@ impl From<core::array::TryFromSliceError> for TbfParseError {
     }
 }
 
-impl fmt::Debug for TbfParseError {
+impl fmt::Display for TbfParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             TbfParseError::NotEnoughFlash => write!(f, "Buffer too short to parse TBF header"),
@@ -101,6 +151,24 @@ impl fmt::Debug for TbfParseError {
                 "Checksum verification failed: app:{app:#x}, calc:{calc:#x}"
             ),
             TbfParseError::BadTlvEntry(tipe) => write!(f, "TLV entry type {tipe} is invalid"),
+            TbfParseError::TlvLengthMismatch {
+                offset,
+                tipe,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "TLV entry type {tipe} at offset {offset} has length {actual}, expected {expected}"
+            ),
+            TbfParseError::TlvOutOfBounds {
+                offset,
+                tipe,
+                length,
+                available,
+            } => write!(
+                f,
+                "TLV entry type {tipe} at offset {offset} has length {length}, but only {available} bytes are left"
+            ),
             TbfParseError::BadProcessName => write!(f, "Process name not UTF-8"),
             TbfParseError::InternalError => write!(f, "Internal kernel error. This is a bug."),
             TbfParseError::TooManyEntries(tipe) => {
@@ -114,10 +182,75 @@ impl fmt::Debug for TbfParseError {
     }
 }
 
+// Keep the messages of the original `Debug` implementation.
+impl fmt::Debug for TbfParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TbfParseError {}
+
+/// Error when encoding a TBF header into bytes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TbfEncodeError {
+    /// The output buffer cannot hold the encoded header. The `usize` is the
+    /// number of bytes required.
+    BufferTooSmall(usize),
+
+    /// The encoded header, or one of its TLV entries, does not fit in the
+    /// 16-bit length fields of the TBF format. The `usize` is the encoded
+    /// length.
+    HeaderTooLarge(usize),
+
+    /// The `total_size` of the TBF object is smaller than its header. The
+    /// `u32` is the offending `total_size`.
+    TotalSizeTooSmall(u32),
+
+    /// More variable length entries were given than the fixed-size buffers
+    /// of the header types can hold. The `usize` is the value of the "tipe"
+    /// field.
+    TooManyEntries(usize),
+
+    /// The package name is longer than the 64 bytes the header can hold.
+    PackageNameTooLong,
+}
+
+impl fmt::Display for TbfEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TbfEncodeError::BufferTooSmall(required) => {
+                write!(f, "Buffer too short, {required} bytes are required")
+            }
+            TbfEncodeError::HeaderTooLarge(length) => {
+                write!(f, "Encoded length {length} does not fit in a TBF header")
+            }
+            TbfEncodeError::TotalSizeTooSmall(total_size) => {
+                write!(f, "Total size {total_size} is smaller than the TBF header")
+            }
+            TbfEncodeError::TooManyEntries(tipe) => {
+                write!(f, "There are too many variable entries of {tipe} to encode")
+            }
+            TbfEncodeError::PackageNameTooLong => write!(f, "The package name is too long."),
+        }
+    }
+}
+
+// Keep the messages of the original `Debug` implementation.
+impl fmt::Debug for TbfEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TbfEncodeError {}
+
 // TBF structure
 
 /// TBF fields that must be present in all v2 headers.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2Base {
     pub(crate) version: u16,
     pub(crate) header_size: u16,
@@ -160,11 +293,11 @@ pub struct TbfTlv {
 /// differ in whether they specify the endpoint of the process binary; Main
 /// Headers do not, while Program Headers do. A TBF with a Main Header cannot
 /// have any Credentials Footers, while a TBF with a Program Header can.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2Main {
-    init_fn_offset: u32,
-    protected_trailer_size: u32,
-    minimum_ram_size: u32,
+    pub(crate) init_fn_offset: u32,
+    pub(crate) protected_trailer_size: u32,
+    pub(crate) minimum_ram_size: u32,
 }
 
 /// The v2 Program Header for apps.
@@ -176,29 +309,29 @@ pub struct TbfHeaderV2Main {
 /// the binary end offset so that a Verifier knows where Credentials Headers
 /// start. The region between the end of the binary and the end of the TBF
 /// is reserved for Credentials Footers.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2Program {
-    init_fn_offset: u32,
-    protected_trailer_size: u32,
-    minimum_ram_size: u32,
-    binary_end_offset: u32,
-    version: u32,
+    pub(crate) init_fn_offset: u32,
+    pub(crate) protected_trailer_size: u32,
+    pub(crate) minimum_ram_size: u32,
+    pub(crate) binary_end_offset: u32,
+    pub(crate) version: u32,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2PackageName<const L: usize> {
-    size: u32,
-    buffer: [u8; L],
+    pub(crate) size: u32,
+    pub(crate) buffer: [u8; L],
 }
 
 /// Writeable flash regions only need an offset and size.
 ///
 /// There can be multiple (or zero) flash regions defined, so this is its own
 /// struct.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub struct TbfHeaderV2WriteableFlashRegion {
-    writeable_flash_region_offset: u32,
-    writeable_flash_region_size: u32,
+    pub(crate) writeable_flash_region_offset: u32,
+    pub(crate) writeable_flash_region_size: u32,
 }
 
 /// Optional fixed addresses for flash and RAM for this process.
@@ -213,54 +346,54 @@ pub struct TbfHeaderV2WriteableFlashRegion {
 /// If this header is included, the kernel will check these values when setting
 /// up the process. If a process wants to set one fixed address but not the other, the unused one
 /// can be set to 0xFFFFFFFF.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub struct TbfHeaderV2FixedAddresses {
     /// The absolute address of the start of RAM that the process expects. For
     /// example, if the process was linked with a RAM region starting at
     /// address `0x00023000`, then this would be set to `0x00023000`.
-    start_process_ram: u32,
+    pub(crate) start_process_ram: u32,
     /// The absolute address of the start of the process binary. This does _not_
     /// include the TBF header. This is the address the process used for the
     /// start of flash with the linker.
-    start_process_flash: u32,
+    pub(crate) start_process_flash: u32,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
-struct TbfHeaderDriverPermission {
-    driver_number: u32,
-    offset: u32,
-    allowed_commands: u64,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct TbfHeaderDriverPermission {
+    pub(crate) driver_number: u32,
+    pub(crate) offset: u32,
+    pub(crate) allowed_commands: u64,
 }
 
 /// A list of permissions for this app
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2Permissions<const L: usize> {
-    length: u16,
-    perms: [TbfHeaderDriverPermission; L],
+    pub(crate) length: u16,
+    pub(crate) perms: [TbfHeaderDriverPermission; L],
 }
 
 /// A list of storage (read/write/modify) permissions for this app.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2StoragePermissions<const L: usize> {
-    write_id: Option<core::num::NonZeroU32>,
-    read_length: u16,
-    read_ids: [u32; L],
-    modify_length: u16,
-    modify_ids: [u32; L],
+    pub(crate) write_id: Option<core::num::NonZeroU32>,
+    pub(crate) read_length: u16,
+    pub(crate) read_ids: [u32; L],
+    pub(crate) modify_length: u16,
+    pub(crate) modify_ids: [u32; L],
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2KernelVersion {
-    major: u16,
-    minor: u16,
+    pub(crate) major: u16,
+    pub(crate) minor: u16,
 }
 
 /// The v2 ShortId for apps.
 ///
 /// Header to specify a fixed ShortID for an app.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2ShortId {
-    short_id: Option<core::num::NonZeroU32>,
+    pub(crate) short_id: Option<core::num::NonZeroU32>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -287,6 +420,123 @@ pub enum TbfFooterV2Credentials {
     EcdsaNistP256(TbfFooterV2Ecdsa<32>),
 }
 
+/// A TLV entry found in the footers of a TBF, as returned by
+/// [`parse_tbf_footers`](crate::parse::parse_tbf_footers).
+///
+/// Offsets are relative to the start of the footers (`binary_end_offset`).
+///
+/// DELTA: Originally did not exist
+#[derive(Clone, Copy, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum TbfFooterTlv<'a> {
+    /// A credentials footer. `length` is the length of the TLV value, without
+    /// the 4 bytes of type and length.
+    Credentials {
+        offset: usize,
+        length: u16,
+        credentials: TbfFooterV2Credentials,
+    },
+
+    /// A footer whose type this parser does not know. It is skipped using its
+    /// length.
+    Unknown {
+        offset: usize,
+        tipe: u16,
+        data: &'a [u8],
+    },
+
+    /// Bytes left at the end of the footers that are too short to hold a TLV.
+    Padding { offset: usize, length: usize },
+}
+
+/// A TLV entry found in a TBF header, as returned by
+/// [`parse_tbf_header_tlvs`](crate::parse::parse_tbf_header_tlvs).
+///
+/// Every entry is returned, including the ones this parser does not know and
+/// the ones it only keeps partially, such as writeable flash regions past the
+/// fourth.
+///
+/// DELTA: Originally did not exist
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TbfHeaderTlv<'a> {
+    /// Raw type of the entry.
+    pub tipe: u16,
+    /// Offset of the entry (of its type field) from the start of the header.
+    pub offset: usize,
+    /// Value of the entry, without the 4 bytes of type and length nor the
+    /// padding to 4 bytes.
+    pub data: &'a [u8],
+}
+
+impl TbfHeaderTlv<'_> {
+    /// Type of the entry, or `TbfHeaderTypes::Unknown` if this parser does
+    /// not know it.
+    pub fn header_type(&self) -> TbfHeaderTypes {
+        match TbfHeaderTypes::try_from(self.tipe) {
+            Ok(tipe) => tipe,
+            Err(_) => TbfHeaderTypes::Unknown,
+        }
+    }
+
+    /// Whether this parser knows the type of the entry.
+    pub fn is_known(&self) -> bool {
+        !matches!(self.header_type(), TbfHeaderTypes::Unknown)
+    }
+
+    /// Offset and size of every region of a writeable flash regions entry.
+    ///
+    /// Unlike [`TbfHeader::get_writeable_flash_region`], this is not limited
+    /// to four regions. Entries of other types have no regions.
+    pub fn writeable_flash_regions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let data = match self.header_type() {
+            TbfHeaderTypes::TbfHeaderWriteableFlashRegions => self.data,
+            _ => &[],
+        };
+        data.chunks_exact(size_of::<TbfHeaderV2WriteableFlashRegion>())
+            .map(|region| {
+                (
+                    u32::from_le_bytes([region[0], region[1], region[2], region[3]]) as usize,
+                    u32::from_le_bytes([region[4], region[5], region[6], region[7]]) as usize,
+                )
+            })
+    }
+}
+
+/// Owned copy of a [`TbfHeaderTlv`], for keeping every TLV of a header next
+/// to the parsed [`TbfHeader`].
+///
+/// DELTA: Originally did not exist
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TbfRawTlv {
+    pub tipe: u16,
+    pub offset: usize,
+    pub data: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl TbfRawTlv {
+    /// Borrow the entry as a [`TbfHeaderTlv`].
+    pub fn as_tlv(&self) -> TbfHeaderTlv<'_> {
+        TbfHeaderTlv {
+            tipe: self.tipe,
+            offset: self.offset,
+            data: &self.data,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<TbfHeaderTlv<'_>> for TbfRawTlv {
+    fn from(tlv: TbfHeaderTlv<'_>) -> Self {
+        TbfRawTlv {
+            tipe: tlv.tipe,
+            offset: tlv.offset,
+            data: tlv.data.to_vec(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct TbfFooterV2SHA<const L: usize> {
     hash: [u8; L],
@@ -842,7 +1092,7 @@ pub enum CommandPermissions {
 /// Note, this struct limits the number of writeable regions an app can have to
 /// four since we need to statically know the length of the array to store in
 /// this type.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TbfHeaderV2 {
     pub(crate) base: TbfHeaderV2Base,
     pub(crate) main: Option<TbfHeaderV2Main>,
@@ -862,7 +1112,7 @@ pub struct TbfHeaderV2 {
 /// in the tock binary, as well as other information about the application.
 /// The kernel can also use this header to keep persistent state about
 /// the application.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 // Clippy suggests we box TbfHeaderV2. We can't really do that, since
 // we are runnning under no_std, and I don't think it's that big of a issue.
 #[allow(clippy::large_enum_variant)]
@@ -0,0 +1,142 @@
+use core::num::NonZeroU32;
+
+use proptest::prelude::*;
+use tbf_parser::encode::TbfEncode;
+use tbf_parser::parse::*;
+use tbf_parser::types::*;
+
+fn parse(buffer: &[u8]) -> TbfHeader {
+    let (version, header_len, _) = parse_tbf_header_lengths(&buffer[0..8].try_into().unwrap())
+        .ok()
+        .unwrap();
+    parse_tbf_header(&buffer[0..header_len as usize], version).unwrap()
+}
+
+#[test]
+fn reencode_flashes() {
+    for buffer in [
+        &include_bytes!("./flashes/simple.dat")[..],
+        &include_bytes!("./flashes/footerSHA256.dat")[..],
+        &include_bytes!("./flashes/footerRSA4096.dat")[..],
+    ] {
+        let header = parse(buffer);
+        let header_len = header.header_size() as usize;
+
+        let mut encoded = [0u8; 256];
+        assert_eq!(header.encoded_len().unwrap(), header_len);
+        assert_eq!(header.encode(&mut encoded).unwrap(), header_len);
+        assert_eq!(&encoded[..header_len], &buffer[..header_len]);
+    }
+}
+
+#[test]
+fn padding() {
+    let header = TbfHeader::new_padding(4096).unwrap();
+
+    let mut encoded = [0u8; 16];
+    assert_eq!(header.encode(&mut encoded).unwrap(), 16);
+
+    let parsed = parse(&encoded);
+    assert!(!parsed.is_app());
+    assert_eq!(parsed, header);
+
+    assert!(TbfHeader::new_padding(8).is_err());
+}
+
+#[test]
+fn encode_errors() {
+    let mut header = TbfHeaderV2::new(32, 1);
+    header.set_main(Some(TbfHeaderV2Main::new(41, 0, 1024)));
+    header.set_package_name(Some("blink")).unwrap();
+
+    let mut buffer = [0u8; 64];
+    assert!(matches!(
+        header.encode(&mut buffer),
+        Err(TbfEncodeError::TotalSizeTooSmall(32))
+    ));
+
+    header.set_total_size(1024);
+    let mut small = [0u8; 16];
+    assert!(matches!(
+        header.encode(&mut small),
+        Err(TbfEncodeError::BufferTooSmall(44))
+    ));
+
+    assert!(matches!(
+        header.set_package_name(Some(&"a".repeat(65))),
+        Err(TbfEncodeError::PackageNameTooLong)
+    ));
+    assert!(header
+        .set_writeable_flash_regions(&[TbfHeaderV2WriteableFlashRegion::new(0, 0); 5])
+        .is_err());
+}
+
+fn driver_permission() -> impl Strategy<Value = TbfHeaderDriverPermission> {
+    (any::<u32>(), any::<u32>(), any::<u64>()).prop_map(|(driver, offset, allowed)| {
+        TbfHeaderDriverPermission::new(driver, offset, allowed)
+    })
+}
+
+prop_compose! {
+    fn app_header()(
+        total_size in 512u32..,
+        flags in any::<u32>(),
+        // Keep the protected region and the end of the binary within the
+        // smallest total size, or the header does not parse.
+        main in prop::option::of((any::<u32>(), 0u32..112, any::<u32>())),
+        program in prop::option::of(
+            (any::<u32>(), 0u32..112, any::<u32>(), 0u32..=512, any::<u32>()),
+        ),
+        package_name in prop::option::of("[a-z_]{0,64}"),
+        regions in prop::collection::vec(any::<(u32, u32)>(), 0..=4),
+        fixed_addresses in prop::option::of(any::<(u32, u32)>()),
+        permissions in prop::option::of(prop::collection::vec(driver_permission(), 0..=8)),
+        storage in prop::option::of((
+            any::<u32>(),
+            prop::collection::vec(any::<u32>(), 0..=8),
+            prop::collection::vec(any::<u32>(), 0..=8),
+        )),
+        kernel_version in prop::option::of(any::<(u16, u16)>()),
+        short_id in prop::option::of(any::<u32>()),
+    ) -> TbfHeaderV2 {
+        let mut header = TbfHeaderV2::new(total_size, flags);
+        // Always include a Main TLV, otherwise the header is parsed as padding.
+        let (init, trailer, ram) = main.unwrap_or((0, 0, 0));
+        header.set_main(Some(TbfHeaderV2Main::new(init, trailer, ram)));
+        header.set_program(program.map(|(init, trailer, ram, end, version)| {
+            TbfHeaderV2Program::new(init, trailer, ram, end, version)
+        }));
+        header.set_package_name(package_name.as_deref()).unwrap();
+        let regions: Vec<_> = regions
+            .into_iter()
+            .map(|(offset, size)| TbfHeaderV2WriteableFlashRegion::new(offset, size))
+            .collect();
+        header.set_writeable_flash_regions(&regions).unwrap();
+        header.set_fixed_addresses(
+            fixed_addresses.map(|(ram, flash)| TbfHeaderV2FixedAddresses::new(ram, flash)),
+        );
+        header.set_permissions(
+            permissions.map(|perms| TbfHeaderV2Permissions::new(&perms).unwrap()),
+        );
+        header.set_storage_permissions(storage.map(|(write_id, read, modify)| {
+            TbfHeaderV2StoragePermissions::new(NonZeroU32::new(write_id), &read, &modify).unwrap()
+        }));
+        header.set_kernel_version(
+            kernel_version.map(|(major, minor)| TbfHeaderV2KernelVersion::new(major, minor)),
+        );
+        header.set_short_id(short_id.map(|id| TbfHeaderV2ShortId::new(NonZeroU32::new(id))));
+        header
+    }
+}
+
+proptest! {
+    #[test]
+    fn round_trip(header in app_header()) {
+        let header = TbfHeader::TbfHeaderV2(header);
+        let mut buffer = [0u8; 512];
+        let length = header.encode(&mut buffer).unwrap();
+
+        prop_assert_eq!(length, header.header_size() as usize);
+        prop_assert_eq!(parse(&buffer), header);
+    }
+}
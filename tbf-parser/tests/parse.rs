@@ -1,5 +1,7 @@
 use tbf_parser::parse::*;
-use tbf_parser::types::{TbfFooterV2Credentials, TbfFooterV2CredentialsType};
+use tbf_parser::types::{
+    TbfFooterTlv, TbfFooterV2Credentials, TbfFooterV2CredentialsType, TbfHeaderTlv, TbfParseError,
+};
 
 #[test]
 fn simple_tbf() {
@@ -124,6 +126,89 @@ fn footer_rsa4096() {
     }
 }
 
+#[test]
+fn footer_iterator() {
+    let buffer: Vec<u8> = include_bytes!("./flashes/footerSHA256.dat").to_vec();
+    let binary_offset = 5836;
+
+    let footers = parse_tbf_footers(&buffer[binary_offset..])
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    dbg!(&footers);
+    assert_eq!(footers.len(), 2);
+    if let TbfFooterTlv::Credentials {
+        offset,
+        length,
+        credentials: TbfFooterV2Credentials::SHA256(_),
+    } = footers[0]
+    {
+        assert_eq!(offset, 0);
+        assert_eq!(length, 36);
+    } else {
+        panic!("Footer is not of type SHA256!");
+    }
+    if let TbfFooterTlv::Credentials {
+        offset,
+        length,
+        credentials: TbfFooterV2Credentials::Reserved(_),
+    } = footers[1]
+    {
+        assert_eq!(offset, 40);
+        assert_eq!(length, 2312);
+    } else {
+        panic!("Footer is not of type 'Reserved'!");
+    }
+}
+
+#[test]
+fn footer_iterator_unknown_and_padding() {
+    let buffer: Vec<u8> = vec![
+        // Unknown footer type 0x90 with 4 bytes of data.
+        0x90, 0x00, 0x04, 0x00, 0x01, 0x02, 0x03, 0x04,
+        // Credentials footer with an unknown format, skipped as an error.
+        0x80, 0x00, 0x04, 0x00, 0x2a, 0x00, 0x00, 0x00, // Reserved credentials.
+        0x80, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // Trailing padding, too short for a TLV.
+        0xff, 0xff,
+    ];
+
+    let mut footers = parse_tbf_footers(&buffer);
+    assert!(matches!(
+        footers.next(),
+        Some(Ok(TbfFooterTlv::Unknown {
+            offset: 0,
+            tipe: 0x90,
+            data: [1, 2, 3, 4],
+        }))
+    ));
+    assert!(matches!(footers.next(), Some(Err(_))));
+    assert!(matches!(
+        footers.next(),
+        Some(Ok(TbfFooterTlv::Credentials {
+            offset: 16,
+            length: 8,
+            credentials: TbfFooterV2Credentials::Reserved(8),
+        }))
+    ));
+    assert!(matches!(
+        footers.next(),
+        Some(Ok(TbfFooterTlv::Padding {
+            offset: 28,
+            length: 2,
+        }))
+    ));
+    assert!(footers.next().is_none());
+}
+
+#[test]
+fn footer_iterator_truncated() {
+    let buffer: Vec<u8> = vec![0x80, 0x00, 0x24, 0x00, 0x03, 0x00, 0x00, 0x00];
+
+    let mut footers = parse_tbf_footers(&buffer);
+    assert!(matches!(footers.next(), Some(Err(_))));
+    assert!(footers.next().is_none());
+}
+
 #[test]
 fn shortid_valid() {
     let buffer: Vec<u8> = vec![
@@ -212,3 +297,167 @@ fn ecdsa_nist_p256_smoke_test() {
         }
     }
 }
+
+/// A header with a Main entry, five writeable flash regions and an entry of
+/// unknown type 0x42 with 3 bytes of data.
+fn header_with_unknown_tlvs() -> Vec<u8> {
+    #[rustfmt::skip]
+    let mut header: Vec<u8> = vec![
+        // Base: version 2, header size, total size 4096, flags, checksum.
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // Main.
+        0x01, 0x00, 0x0c, 0x00, 0x29, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00,
+        // Unknown, padded to 4 bytes.
+        0x42, 0x00, 0x03, 0x00, 0xaa, 0xbb, 0xcc, 0x00,
+        // Writeable flash regions.
+        0x02, 0x00, 0x28, 0x00,
+    ];
+    for region in 0..5u32 {
+        header.extend_from_slice(&(0x800 + region * 0x100).to_le_bytes());
+        header.extend_from_slice(&0x100u32.to_le_bytes());
+    }
+
+    let header_size = header.len() as u16;
+    header[2..4].copy_from_slice(&header_size.to_le_bytes());
+    fix_checksum(&mut header);
+    header
+}
+
+fn fix_checksum(header: &mut [u8]) {
+    let checksum = header
+        .chunks_exact(4)
+        .enumerate()
+        .filter(|(i, _)| *i != 3)
+        .fold(0, |checksum, (_, word)| {
+            checksum ^ u32::from_le_bytes(word.try_into().unwrap())
+        });
+    header[12..16].copy_from_slice(&checksum.to_le_bytes());
+}
+
+#[test]
+fn header_tlv_iterator() {
+    let header = header_with_unknown_tlvs();
+
+    let tlvs = parse_tbf_header_tlvs(&header)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    dbg!(&tlvs);
+    assert_eq!(tlvs.len(), 3);
+    assert_eq!((tlvs[0].tipe, tlvs[0].offset), (1, 16));
+    assert!(tlvs[0].is_known());
+    assert_eq!(
+        tlvs[1],
+        TbfHeaderTlv {
+            tipe: 0x42,
+            offset: 32,
+            data: &[0xaa, 0xbb, 0xcc],
+        }
+    );
+    assert!(!tlvs[1].is_known());
+    assert_eq!((tlvs[2].tipe, tlvs[2].offset), (2, 40));
+
+    // The typed header only keeps four regions, the TLV has all of them.
+    let parsed = parse_tbf_header(&header, 2).unwrap();
+    assert_eq!(parsed.number_writeable_flash_regions(), 4);
+    let regions: Vec<_> = tlvs[2].writeable_flash_regions().collect();
+    assert_eq!(regions.len(), 5);
+    assert_eq!(regions[4], (0xc00, 0x100));
+    assert_eq!(tlvs[1].writeable_flash_regions().count(), 0);
+}
+
+#[test]
+fn header_tlv_iterator_truncated() {
+    let header = header_with_unknown_tlvs();
+
+    let mut tlvs = parse_tbf_header_tlvs(&header[..header.len() - 4]);
+    assert!(matches!(tlvs.next(), Some(Ok(_))));
+    assert!(matches!(tlvs.next(), Some(Ok(_))));
+    assert!(matches!(tlvs.next(), Some(Err(_))));
+    assert!(tlvs.next().is_none());
+
+    let mut tlvs = parse_tbf_header_tlvs(&header[..8]);
+    assert!(matches!(tlvs.next(), Some(Err(_))));
+    assert!(tlvs.next().is_none());
+
+    // A padding header has no entries.
+    assert!(parse_tbf_header_tlvs(&header[..16]).next().is_none());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn header_raw_tlvs() {
+    let header = header_with_unknown_tlvs();
+
+    let raw = parse_tbf_header_raw(&header).unwrap();
+    let tlvs: Vec<_> = parse_tbf_header_tlvs(&header).map(Result::unwrap).collect();
+    assert_eq!(raw.len(), tlvs.len());
+    for (raw, tlv) in raw.iter().zip(tlvs) {
+        assert_eq!(raw.as_tlv(), tlv);
+    }
+}
+
+/// Regression fixtures for headers that made the accessors or callers overflow.
+#[test]
+fn out_of_bounds_sizes() {
+    // Main header with a protected trailer size of 0xFFFFFFFF.
+    let buffer = include_bytes!("./flashes/trailerOverflow.dat");
+    let (ver, header_len, _) = parse_tbf_header_lengths(&buffer[0..8].try_into().unwrap())
+        .ok()
+        .unwrap();
+    assert_eq!(
+        parse_tbf_header(&buffer[0..header_len as usize], ver).unwrap_err(),
+        TbfParseError::BadTlvEntry(1)
+    );
+
+    // Program header with a binary end offset past the total size.
+    let buffer = include_bytes!("./flashes/binaryEndOverflow.dat");
+    let (ver, header_len, _) = parse_tbf_header_lengths(&buffer[0..8].try_into().unwrap())
+        .ok()
+        .unwrap();
+    assert_eq!(
+        parse_tbf_header(&buffer[0..header_len as usize], ver).unwrap_err(),
+        TbfParseError::BadTlvEntry(9)
+    );
+}
+
+#[test]
+fn error_context() {
+    // Main entry with a length of 8 instead of 12.
+    let mut header = header_with_unknown_tlvs();
+    header[18] = 8;
+    fix_checksum(&mut header);
+    let error = parse_tbf_header(&header, 2).unwrap_err();
+    assert_eq!(
+        error,
+        TbfParseError::TlvLengthMismatch {
+            offset: 16,
+            tipe: 1,
+            expected: 12,
+            actual: 8,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "TLV entry type 1 at offset 16 has length 8, expected 12"
+    );
+
+    // Writeable flash regions entry cut short.
+    let mut header = header_with_unknown_tlvs();
+    header.truncate(header.len() - 8);
+    let header_size = header.len() as u16;
+    header[2..4].copy_from_slice(&header_size.to_le_bytes());
+    fix_checksum(&mut header);
+    let out_of_bounds = TbfParseError::TlvOutOfBounds {
+        offset: 40,
+        tipe: 2,
+        length: 40,
+        available: 32,
+    };
+    assert_eq!(parse_tbf_header(&header, 2).unwrap_err(), out_of_bounds);
+    assert_eq!(
+        parse_tbf_header_tlvs(&header).last(),
+        Some(Err(out_of_bounds))
+    );
+}
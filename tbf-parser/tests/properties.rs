@@ -0,0 +1,140 @@
+//! Property tests feeding generated and arbitrary bytes to the parser. Parsing
+//! must either fail with a `TbfParseError` or return a header whose accessors
+//! do not panic.
+
+use proptest::prelude::*;
+use tbf_parser::parse::*;
+use tbf_parser::types::*;
+
+/// Update the checksum of a header, so that parsing goes past the checksum
+/// check.
+fn fix_checksum(header: &mut [u8]) {
+    let checksum = header
+        .chunks_exact(4)
+        .enumerate()
+        .filter(|(i, _)| *i != 3)
+        .fold(0, |checksum, (_, word)| {
+            checksum ^ u32::from_le_bytes(word.try_into().unwrap())
+        });
+    header[12..16].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Call every accessor of a parsed header.
+fn access_all(header: &TbfHeader) {
+    let _ = header.length();
+    let _ = header.is_app();
+    let _ = header.enabled();
+    let _ = header.sticky();
+    let _ = header.total_size();
+    let _ = header.checksum();
+    let _ = header.get_application_flags();
+    let _ = header.header_size();
+    let _ = header.get_minimum_app_ram_size();
+    let _ = header.get_protected_trailer_size();
+    let _ = header.get_app_start_offset();
+    let _ = header.get_protected_region_size();
+    let _ = header.get_init_function_offset();
+    let _ = header.get_package_name();
+    for i in 0..=header.number_writeable_flash_regions() {
+        let _ = header.get_writeable_flash_region(i);
+    }
+    let _ = header.get_fixed_address_ram();
+    let _ = header.get_fixed_address_flash();
+    let _ = header.get_command_permissions(1, 0);
+    let _ = header.get_storage_write_id();
+    let _ = header.get_storage_read_ids();
+    let _ = header.get_storage_modify_ids();
+    let _ = header.get_kernel_version();
+    let _ = header.get_binary_end();
+    let _ = header.get_binary_version();
+    let _ = header.get_tbf_version();
+    let _ = header.get_fixed_short_id();
+}
+
+/// A TLV entry of any known header type (and a few unknown ones) with
+/// arbitrary contents, padded to 4 bytes.
+fn tlv() -> impl Strategy<Value = Vec<u8>> {
+    (0u16..12, prop::collection::vec(any::<u8>(), 0..80)).prop_map(|(tipe, data)| {
+        let mut tlv = tipe.to_le_bytes().to_vec();
+        tlv.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        tlv.extend_from_slice(&data);
+        tlv.resize(tlv.len().next_multiple_of(4), 0);
+        tlv
+    })
+}
+
+/// A version 2 header with a correct `header_size`, arbitrary base fields and
+/// arbitrary TLV entries. The checksum is only correct if `valid_checksum`.
+fn header() -> impl Strategy<Value = Vec<u8>> {
+    (
+        any::<[u8; 8]>(),
+        prop::collection::vec(tlv(), 0..6),
+        any::<bool>(),
+    )
+        .prop_map(|(base, tlvs, valid_checksum)| {
+            let mut header = vec![2, 0, 0, 0];
+            header.extend_from_slice(&base);
+            header.extend_from_slice(&[0; 4]);
+            header.extend(tlvs.concat());
+
+            let header_size = header.len() as u16;
+            header[2..4].copy_from_slice(&header_size.to_le_bytes());
+            if valid_checksum {
+                fix_checksum(&mut header);
+            }
+            header
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2048))]
+
+    #[test]
+    fn generated_headers(header in header()) {
+        if let Ok((version, header_size, _)) =
+            parse_tbf_header_lengths(&header[0..8].try_into().unwrap())
+        {
+            prop_assert_eq!(header_size as usize, header.len());
+            if let Ok(parsed) = parse_tbf_header(&header, version) {
+                access_all(&parsed);
+            }
+        }
+        if let Ok(parsed) = parse_tbf_header(&header, 2) {
+            access_all(&parsed);
+        }
+        for tlv in parse_tbf_header_tlvs(&header) {
+            let _ = tlv.map(|tlv| tlv.writeable_flash_regions().count());
+        }
+    }
+
+    #[test]
+    fn arbitrary_bytes(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+        if let Some(lengths) = bytes.get(0..8) {
+            let _ = parse_tbf_header_lengths(lengths.try_into().unwrap());
+        }
+        if let Ok(parsed) = parse_tbf_header(&bytes, 2) {
+            access_all(&parsed);
+        }
+        let _ = parse_tbf_footer(&bytes);
+        for footer in parse_tbf_footers(&bytes) {
+            let _ = footer;
+        }
+    }
+
+    #[test]
+    fn credentials_footers(
+        format in 0u32..8,
+        length in 0u16..1100,
+        data in prop::collection::vec(any::<u8>(), 0..1100),
+    ) {
+        let mut footer = (TbfHeaderTypes::TbfFooterCredentials as u16).to_le_bytes().to_vec();
+        footer.extend_from_slice(&length.to_le_bytes());
+        footer.extend_from_slice(&format.to_le_bytes());
+        footer.extend_from_slice(&data);
+
+        if let Ok((credentials, size)) = parse_tbf_footer(&footer) {
+            prop_assert_eq!(size, length as u32);
+            let _ = credentials.get_type();
+        }
+    }
+}
@@ -3,7 +3,7 @@
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
 use clap::error::ErrorKind;
-use clap::{arg, crate_version, value_parser, ArgMatches, Command};
+use clap::{arg, crate_version, value_parser, ArgAction, ArgMatches, Command};
 
 use crate::known_boards::list_known_board_names;
 
@@ -47,17 +47,97 @@ fn get_subcommands() -> Vec<Command> {
             .about("Verbose information about the connected board")
             .args(get_app_args())
             .args(get_channel_args())
+            .args(get_credentials_args())
             .arg_required_else_help(false),
         Command::new("install")
             .about("Install apps")
             .args(get_app_args())
             .args(get_channel_args())
+            .args(get_reset_args())
             .arg_required_else_help(false),
         Command::new("erase-apps")
             .about("Erase apps")
             .args(get_app_args())
             .args(get_channel_args())
+            .args(get_reset_args())
             .arg_required_else_help(false),
+        Command::new("reset")
+            .about("Reset the board")
+            .args([arg!(--halt "Halt the core (probe) or stay in the bootloader (serial) after the reset")
+                .action(ArgAction::SetTrue)])
+            .args(get_channel_args()),
+        Command::new("get-attribute")
+            .about("Read a bootloader attribute")
+            .args([arg!(<key> "Key of the attribute")])
+            .args(get_channel_args()),
+        Command::new("set-attribute")
+            .about("Add or update a bootloader attribute")
+            .args([
+                arg!(<key> "Key of the attribute, at most 8 bytes long"),
+                arg!(<value> "Value of the attribute, at most 55 bytes long"),
+            ])
+            .args(get_channel_args()),
+        Command::new("remove-attribute")
+            .about("Remove a bootloader attribute")
+            .args([arg!(<key> "Key of the attribute")])
+            .args(get_channel_args()),
+        Command::new("flash-bootloader")
+            .about("Install or update the Tock bootloader")
+            .args([
+                arg!(<binary> "Path of the bootloader binary"),
+                arg!(--address <ADDRESS> "Address where the bootloader is written")
+                    .value_parser(parse_address)
+                    .default_value("0x0"),
+                arg!(--force "Allow downgrading the bootloader").action(ArgAction::SetTrue),
+            ])
+            .args(get_channel_args()),
+        Command::new("flash-kernel")
+            .about("Install a Tock kernel, from a binary or an ELF file")
+            .args([
+                arg!(<kernel> "Path of the kernel binary or ELF file"),
+                arg!(--force "Write an ELF kernel at its link address, even if the board expects another one")
+                    .action(ArgAction::SetTrue),
+            ])
+            .args(get_channel_args()),
+        Command::new("read-external")
+            .about("Read the external flash of the board")
+            .args([
+                arg!(<address> "Address of the data in external flash").value_parser(parse_address),
+                arg!(<length> "Number of bytes to read").value_parser(parse_size),
+                arg!(-o --output <OUTPUT> "File where the data is written. Defaults to printing it as hex"),
+            ])
+            .args(get_channel_args()),
+        Command::new("write-external")
+            .about("Write a file to the external flash of the board")
+            .args([
+                arg!(<address> "Address in external flash, aligned to a 512-byte page")
+                    .value_parser(parse_address),
+                arg!(<binary> "Path of the data to write"),
+            ])
+            .args(get_channel_args()),
+        Command::new("erase-external")
+            .about("Erase part of the external flash of the board")
+            .args([
+                arg!(<address> "Address in external flash, aligned to a 512-byte page")
+                    .value_parser(parse_address),
+                arg!(<length> "Number of bytes to erase, a multiple of 512").value_parser(parse_size),
+            ])
+            .args(get_channel_args()),
+        Command::new("inspect-tab")
+            .about("Show the contents of a TAB file without a board attached")
+            .args([
+                arg!(<tab> "Path of the tab file (or bare .tbf) to inspect, or '-' to read from stdin"),
+                arg!(--arch <ARCH> "Architecture to report for a bare TBF"),
+            ])
+            .args(get_credentials_args()),
+        Command::new("sign")
+            .about("Add a credentials footer, signed with a private key, to the apps in a TAB or TBF")
+            .args([
+                arg!(<input> "Path of the tab file or bare .tbf to sign"),
+                arg!(--key <KEY> "Private key (ECDSA P-256 or RSA, PEM or DER) used to sign")
+                    .required(true),
+                arg!(-o --output <OUTPUT> "Where to write the signed file. Defaults to overwriting the input"),
+            ]),
     ]
 }
 
@@ -69,16 +149,31 @@ fn get_app_args() -> Vec<clap::Arg> {
         // Default of ProbeTargetInfo: 0x00030000
         arg!(-a --"app-address" <ADDRESS> "Address where apps are located")
             .conflicts_with_all(probe_args_ids.clone().collect::<Vec<_>>()),
-        arg!(--tab <TAB> "Specify the path of the tab file"),
+        arg!(--tab <TAB> "Specify the path of the tab file, of a bare .tbf file, or '-' to read from stdin"),
+        arg!(--arch <ARCH> "Architecture of a bare TBF given to '--tab'. Defaults to the board architecture"),
     ]
     // Note: the .action(clap::ArgAction::SetTrue) doesn't seem to be necessary, though in clap documentation it is used.
 }
 
+/// Generate all of the [arguments](clap::Arg) used by subcommands which can reset the board once
+/// they are done.
+fn get_reset_args() -> Vec<clap::Arg> {
+    vec![
+        arg!(--"reset-after" "Reset the board and start the kernel once done")
+            .action(ArgAction::SetTrue),
+    ]
+}
+
+/// Generate all of the [arguments](clap::Arg) used to check the credentials of apps.
+fn get_credentials_args() -> Vec<clap::Arg> {
+    vec![arg!(--"public-key" <KEY> "Public key (PEM, DER or raw RSA modulus) used to check app signatures. Can be repeated")
+        .action(ArgAction::Append)]
+}
+
 /// Generate all of the [arguments](clap::Arg) that are required by subcommands which work
 /// with channels and computer-board communication.
 fn get_channel_args() -> Vec<clap::Arg> {
     let probe_args_ids = get_probe_args_ids().into_iter();
-    let serial_args_ids = get_serial_args_ids().into_iter();
 
     let known_board_names = list_known_board_names()
         .into_iter()
@@ -89,14 +184,10 @@ fn get_channel_args() -> Vec<clap::Arg> {
         arg!(--serial "Use the serial bootloader to flash")
             .action(clap::ArgAction::SetTrue)
             .conflicts_with_all(probe_args_ids.clone().collect::<Vec<_>>()),
+        // The other connection options refine the defaults of the board.
         arg!(--board <BOARD> "Explicitly specify the board that is being targeted")
             .value_parser(known_board_names)
-            .conflicts_with_all(
-                serial_args_ids
-                    .clone()
-                    .chain(probe_args_ids.clone())
-                    .collect::<Vec<_>>(),
-            ),
+            .conflicts_with_all(["chip", "core"]),
     ]
     .into_iter()
     .chain(get_probe_args())
@@ -110,8 +201,16 @@ fn get_probe_args() -> Vec<clap::Arg> {
     vec![
         // Conditionally required via custom validation
         arg!(--chip <CHIP> "Explicitly specify the chip"),
+        arg!(--probe <PROBE> "The debug probe to use: a serial number, a vid:pid pair or an index in the list of probes"),
         // Default of ProbeTargetInfo: 0
         arg!(--core <CORE> "Explicitly specify the core").value_parser(clap::value_parser!(usize)),
+        arg!(--"wire-protocol" <PROTOCOL> "Protocol used by the probe. Defaults to the probe's choice, usually SWD")
+            .value_parser(["swd", "jtag"]),
+        arg!(--speed <KHZ> "Clock speed of the probe, in kHz").value_parser(clap::value_parser!(u32)),
+        arg!(--"connect-under-reset" "Hold the chip in reset while attaching the probe")
+            .action(ArgAction::SetTrue),
+        arg!(--"allow-erase-all" "Allow mass erasing a locked chip to unlock it. This erases everything on the chip")
+            .action(ArgAction::SetTrue),
     ]
     .into_iter()
     .map(|arg| arg.conflicts_with_all(serial_args_ids.clone().collect::<Vec<_>>()))
@@ -120,17 +219,28 @@ fn get_probe_args() -> Vec<clap::Arg> {
 }
 
 fn get_probe_args_ids() -> Vec<clap::Id> {
-    vec!["chip".into(), "core".into()]
+    vec![
+        "chip".into(),
+        "probe".into(),
+        "core".into(),
+        "wire-protocol".into(),
+        "speed".into(),
+        "connect-under-reset".into(),
+        "allow-erase-all".into(),
+    ]
 }
 
 fn get_serial_args() -> Vec<clap::Arg> {
     let probe_args_ids = get_probe_args_ids().into_iter();
 
     vec![
-        arg!(-p --port <PORT> "The serial port or device name to use"),
+        arg!(-p --port <PORT> "The serial port to use: a device name, a USB serial number, a vid:pid pair or an index in the list of ports"),
         // Default of SerialTargetInfo: 115200
         arg!(--"baud-rate" <RATE> "If using serial, set the target baud rate")
             .value_parser(value_parser!(u32)),
+        // Default of RetryPolicy: 3
+        arg!(--retries <COUNT> "If using serial, how many times to resend a bootloader command after a transient error")
+            .value_parser(value_parser!(u32)),
         // TODO: add more serial arguments to match with SerialTargetInfo
     ]
     .into_iter()
@@ -140,7 +250,25 @@ fn get_serial_args() -> Vec<clap::Arg> {
 }
 
 fn get_serial_args_ids() -> Vec<clap::Id> {
-    vec!["port".into(), "baud-rate".into()]
+    vec!["port".into(), "baud-rate".into(), "retries".into()]
+}
+
+/// Parse an address given either in hexadecimal (with a `0x` prefix) or in
+/// decimal.
+fn parse_address(address: &str) -> Result<u64, String> {
+    parse_number(address).map_err(|e| format!("invalid address '{address}': {e}"))
+}
+
+/// Parse a size in bytes, in the same formats as [parse_address].
+fn parse_size(size: &str) -> Result<u64, String> {
+    parse_number(size).map_err(|e| format!("invalid size '{size}': {e}"))
+}
+
+fn parse_number(value: &str) -> Result<u64, std::num::ParseIntError> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
 }
 
 pub fn validate(cmd: &mut Command, user_options: &ArgMatches) {
@@ -184,4 +312,15 @@ mod test {
         assert_eq!(probe_args_ids, probe_args);
         assert_eq!(serial_args_ids, serial_args);
     }
+
+    #[test]
+    fn addresses() {
+        use super::*;
+
+        assert_eq!(parse_address("0x40000"), Ok(0x40000));
+        assert_eq!(parse_address("1024"), Ok(1024));
+        assert!(parse_address("0xZZ").is_err());
+        assert_eq!(parse_size("0x200"), Ok(512));
+        assert!(parse_size("-1").is_err());
+    }
 }
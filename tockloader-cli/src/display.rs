@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
+use tbf_parser::types::{TbfHeader, TbfRawTlv};
 use tockloader_lib::attributes::app_attributes::AppAttributes;
+use tockloader_lib::attributes::bootloader_info::BootloaderInfo;
 use tockloader_lib::attributes::system_attributes::SystemAttributes;
+use tockloader_lib::credentials::verify::{AppVerification, CredentialsCheck};
+use tockloader_lib::tabs::tab::Tab;
 
 // ANSI escape codes for colors
 const RESET: &str = "\x1b[0m";
@@ -44,8 +48,19 @@ pub async fn print_list(app_details: &[AppAttributes]) {
     }
 }
 
-pub async fn print_info(app_details: &mut [AppAttributes], system_details: &mut SystemAttributes) {
+pub async fn print_info(
+    app_details: &mut [AppAttributes],
+    system_details: &mut SystemAttributes,
+    verifications: &[AppVerification],
+    bootloader: Option<&BootloaderInfo>,
+    board_id: Option<&[u8]>,
+) {
     for (i, details) in app_details.iter().enumerate() {
+        let credentials = verifications
+            .iter()
+            .find(|verification| verification.address == details.address)
+            .map_or(&[][..], |verification| &verification.credentials[..]);
+
         println!("\n{RESET}{BOLD_MAGENTA} ┏━━━━━━━━━━━━━━━━┓");
         println!("{RESET}{BOLD_RED} ┃ {RESET}{BOLD_GREEN} App_{i:<9} {RESET}{BOLD_RED}┃");
         println!("{RESET}{BOLD_YELLOW} ┗━━━━━━━━━━━━━━━━┛");
@@ -173,6 +188,24 @@ pub async fn print_info(app_details: &mut [AppAttributes], system_details: &mut
             details.tbf_header.get_kernel_version().unwrap().1,
         );
 
+        for tlv in details.tbf_tlvs.iter().map(TbfRawTlv::as_tlv) {
+            let regions: Vec<(usize, usize)> = tlv.writeable_flash_regions().collect();
+            if !regions.is_empty() {
+                println!(" {BOLD_GREEN}    TLV: Writeable Flash Regions (2){RESET}");
+                for (offset, size) in regions {
+                    println!(
+                        " {BOLD_GREEN}        offset, size:          {RESET}{offset:#x}, {size}",
+                    );
+                }
+            } else if !tlv.is_known() {
+                println!(" {BOLD_GREEN}    TLV: Unknown ({}){RESET}", tlv.tipe);
+                println!(
+                    " {BOLD_GREEN}        data:                  {RESET}{}",
+                    hex(tlv.data),
+                );
+            }
+        }
+
         println!("\n {BOLD_GREEN}    Footer{RESET}");
 
         let mut total_footer_size: u32 = 0;
@@ -199,34 +232,172 @@ pub async fn print_info(app_details: &mut [AppAttributes], system_details: &mut
                 " {BOLD_GREEN}        Length:                {RESET}{}",
                 footer_details.size - 4,
             );
+
+            if let Some(check) = credentials.iter().find(|check| check.index == j) {
+                println!(
+                    " {BOLD_GREEN}        Status:                {RESET}{}",
+                    check.status,
+                );
+            }
+        }
+    }
+
+    if bootloader.is_some() || board_id.is_some() {
+        println!("\n\n{BOLD_GREEN} Bootloader{RESET}");
+        if let Some(id) = board_id {
+            println!("{BOLD_GREEN}    board_id:          {RESET}{}", hex(id));
+        }
+        for (key, value) in bootloader.iter().flat_map(|info| &info.fields) {
+            println!("{BOLD_GREEN}    {:<19}{RESET}{value}", format!("{key}:"));
         }
     }
 
     println!("\n\n{BOLD_GREEN} Kernel Attributes{RESET}");
+    let (Some(sentinel), Some(kernel_version)) =
+        (&system_details.sentinel, system_details.kernel_version)
+    else {
+        println!("{BOLD_GREEN}    Not found{RESET}\n\n");
+        return;
+    };
+    println!("{BOLD_GREEN}    Sentinel:          {sentinel:<10}{RESET}");
+    println!("{BOLD_GREEN}    Version:           {kernel_version:<10}{RESET}");
+    if let (Some(start), Some(len)) = (system_details.app_mem_start, system_details.app_mem_len) {
+        println!("{BOLD_GREEN} KATLV: APP Memory{RESET}");
+        println!("{BOLD_GREEN}    app_memory_start:  {start:<10}{RESET}");
+        println!("{BOLD_GREEN}    app_memory_len:    {len:<10}{RESET}");
+    }
+    if let (Some(start), Some(len)) = (
+        system_details.kernel_bin_start,
+        system_details.kernel_bin_len,
+    ) {
+        println!("{BOLD_GREEN} KATLV: Kernel Binary{RESET}");
+        println!("{BOLD_GREEN}    kernel_binary_start: {start:<10}{RESET}");
+        println!("{BOLD_GREEN}    kernel_binary_len:   {len:<10}{RESET}");
+    }
+    println!("\n");
+}
+
+pub async fn print_tab(
+    tab: &Tab,
+    tbf_headers: &[TbfHeader],
+    credentials: &[Vec<CredentialsCheck>],
+) {
+    println!("\n{RESET}{BOLD_MAGENTA} ┏━━━━━━━━━━━━━━━━┓");
+    println!("{RESET}{BOLD_RED} ┃ {RESET}{BOLD_GREEN} TAB           {RESET}{BOLD_RED}┃");
+    println!("{RESET}{BOLD_YELLOW} ┗━━━━━━━━━━━━━━━━┛");
+
+    println!("\n {BOLD_GREEN} Name:             {RESET}{}", tab.name());
     println!(
-        "{BOLD_GREEN}    Sentinel:          {:<10}{RESET}",
-        system_details.sentinel.clone().unwrap(),
-    );
-    println!(
-        "{BOLD_GREEN}    Version:           {:<10}{RESET}",
-        system_details.kernel_version.unwrap(),
+        " {BOLD_GREEN} Build date:       {RESET}{}",
+        tab.build_date()
     );
-    println!("{BOLD_GREEN} KATLV: APP Memory{RESET}");
     println!(
-        "{BOLD_GREEN}    app_memory_start:  {:<10}{RESET}",
-        system_details.app_mem_start.unwrap(),
+        " {BOLD_GREEN} TAB version:      {RESET}{}",
+        tab.metadata().tab_version,
     );
     println!(
-        "{BOLD_GREEN}    app_memory_len:    {:<10}{RESET}",
-        system_details.app_mem_len.unwrap(),
+        " {BOLD_GREEN} Minimum kernel:   {RESET}{}",
+        tab.minimum_kernel_version(),
     );
-    println!("{BOLD_GREEN} KATLV: Kernel Binary{RESET}");
     println!(
-        "{BOLD_GREEN}    kernel_binary_start: {:<10}{RESET}",
-        system_details.kernel_bin_start.unwrap(),
+        " {BOLD_GREEN} Only for boards:  {RESET}{}",
+        tab.only_for_boards()
+            .map_or("any".to_owned(), |boards| boards.join(", ")),
     );
     println!(
-        "{BOLD_GREEN}    kernel_binary_len:   {:<10}{RESET}\n\n",
-        system_details.kernel_bin_len.unwrap(),
+        " {BOLD_GREEN} Architectures:    {RESET}{}",
+        tab.architectures().join(", "),
     );
+
+    for (key, value) in tab.metadata().extra.iter() {
+        println!(" {BOLD_GREEN} {:<17} {RESET}{value}", format!("{key}:"));
+    }
+
+    for ((file, header), credentials) in tab.tbf_files().iter().zip(tbf_headers).zip(credentials) {
+        println!("\n {BOLD_GREEN} TBF: {}{RESET}", file.filename());
+        println!(" {BOLD_GREEN}    arch:          {RESET}{}", file.arch());
+        println!(
+            " {BOLD_GREEN}    package_name:  {RESET}{}",
+            header.get_package_name().unwrap_or("-"),
+        );
+        println!(
+            " {BOLD_GREEN}    app_version:   {RESET}{}",
+            header.get_binary_version(),
+        );
+        println!(
+            " {BOLD_GREEN}    TBF version:   {RESET}{}",
+            header.get_tbf_version(),
+        );
+        println!(
+            " {BOLD_GREEN}    header_size:   {RESET}{}",
+            header.header_size(),
+        );
+        println!(
+            " {BOLD_GREEN}    total_size:    {RESET}{}",
+            header.total_size(),
+        );
+        println!(
+            " {BOLD_GREEN}    checksum:      {RESET}{:#x}",
+            header.checksum(),
+        );
+        println!(
+            " {BOLD_GREEN}    flags:         {RESET}{:#b}",
+            header.get_application_flags(),
+        );
+        println!(
+            " {BOLD_GREEN}        enabled:       {RESET}{}",
+            header.enabled(),
+        );
+        println!(
+            " {BOLD_GREEN}        sticky:        {RESET}{}",
+            header.sticky(),
+        );
+        println!(
+            " {BOLD_GREEN}    init_fn_offset:           {RESET}{}",
+            header.get_init_function_offset(),
+        );
+        println!(
+            " {BOLD_GREEN}    protected_trailer_size:   {RESET}{}",
+            header.get_protected_trailer_size(),
+        );
+        println!(
+            " {BOLD_GREEN}    minimum_ram_size:         {RESET}{}",
+            header.get_minimum_app_ram_size(),
+        );
+        println!(
+            " {BOLD_GREEN}    binary_end_offset:        {RESET}{}",
+            header.get_binary_end(),
+        );
+        if let Some((major, minor)) = header.get_kernel_version() {
+            println!(" {BOLD_GREEN}    kernel_version:           {RESET}{major}.{minor}");
+        }
+        if let Some(flash) = header.get_fixed_address_flash() {
+            println!(" {BOLD_GREEN}    fixed_address_flash:      {RESET}{flash:#x}");
+        }
+        if let Some(ram) = header.get_fixed_address_ram() {
+            println!(" {BOLD_GREEN}    fixed_address_ram:        {RESET}{ram:#x}");
+        }
+        for check in credentials {
+            println!(
+                " {BOLD_GREEN}    Footer [{}] {:?}:{RESET} {}",
+                check.index, check.format, check.status,
+            );
+        }
+    }
+    println!();
+}
+
+/// Print `data`, read at `address`, 16 bytes per line.
+pub fn print_hex_dump(address: u64, data: &[u8]) {
+    for (i, line) in data.chunks(16).enumerate() {
+        println!("{:#010x}  {}", address + i as u64 * 16, hex(line));
+    }
+}
+
+/// Format bytes as space-separated hex pairs.
+fn hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<String>>()
+        .join(" ")
 }
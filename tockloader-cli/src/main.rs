@@ -5,53 +5,95 @@
 mod cli;
 mod display;
 mod known_boards;
+mod selection;
 
-use anyhow::{Context, Result};
+use std::io::Read;
+
+use anyhow::{bail, Context, Result};
 use clap::ArgMatches;
 use cli::make_cli;
 use known_boards::KnownBoardNames;
+use selection::{select_port, select_probe};
+use tbf_parser::parse::parse_tbf_header_lengths;
 use tockloader_lib::board_settings::BoardSettings;
 use tockloader_lib::connection::{
     Connection, ProbeRSConnection, ProbeTargetInfo, SerialConnection, SerialTargetInfo,
     TockloaderConnection,
 };
+use tockloader_lib::credentials::keys::{PrivateKey, PublicKey};
+use tockloader_lib::credentials::sign::sign_tbf;
+use tockloader_lib::credentials::verify::verify_tbf;
+use tockloader_lib::kernel::KernelImage;
 use tockloader_lib::known_boards::KnownBoard;
+use tockloader_lib::tabs::builder::TabBuilder;
 use tockloader_lib::tabs::tab::Tab;
 use tockloader_lib::{
-    list_debug_probes, list_serial_ports, CommandEraseApps, CommandInfo, CommandInstall,
-    CommandList,
+    list_debug_probes, list_serial_ports, CommandAttributes, CommandEraseApps,
+    CommandExternalFlash, CommandFlashBootloader, CommandFlashKernel, CommandInfo, CommandInstall,
+    CommandList, CommandReset, CommandVerify, ResetMode,
 };
 
-fn get_serial_target_info(user_options: &ArgMatches) -> SerialTargetInfo {
-    let board = get_known_board(user_options);
-    if let Some(board) = board {
-        return board.serial_target_info();
-    }
+fn load_public_keys(user_options: &ArgMatches) -> Result<Vec<PublicKey>> {
+    user_options
+        .get_many::<String>("public-key")
+        .unwrap_or_default()
+        .map(|path| {
+            PublicKey::from_file(path).with_context(|| format!("Failed to load public key {path}."))
+        })
+        .collect()
+}
 
-    let mut result = SerialTargetInfo::default();
+fn get_serial_target_info(user_options: &ArgMatches) -> SerialTargetInfo {
+    let mut result = match get_known_board(user_options) {
+        Some(board) => board.serial_target_info(),
+        None => SerialTargetInfo::default(),
+    };
 
     if let Some(baud_rate) = user_options.get_one::<u32>("baud-rate") {
         result.baud_rate = *baud_rate;
     }
 
+    if let Some(retries) = user_options.get_one::<u32>("retries") {
+        result.retry.retries = *retries;
+    }
+
     result
 }
 
 fn get_probe_target_info(user_options: &ArgMatches) -> ProbeTargetInfo {
-    let board = get_known_board(user_options);
-    if let Some(board) = board {
-        return board.probe_target_info();
+    let mut result = match get_known_board(user_options) {
+        Some(board) => board.probe_target_info(),
+        None => {
+            let chip = user_options
+                .get_one::<String>("chip")
+                .expect("Expected validation to catch missing chip")
+                .clone();
+            ProbeTargetInfo::default(chip)
+        }
+    };
+
+    if let Some(core) = user_options.get_one::<usize>("core") {
+        result.core = *core;
+    }
+
+    if let Some(protocol) = user_options.get_one::<String>("wire-protocol") {
+        result.protocol = Some(
+            protocol
+                .parse()
+                .expect("Expected clap to only accept known protocols"),
+        );
     }
 
-    let chip = user_options
-        .get_one::<String>("chip")
-        .expect("Expected validation to catch missing chip")
-        .clone();
+    if let Some(speed) = user_options.get_one::<u32>("speed") {
+        result.speed_khz = Some(*speed);
+    }
 
-    let mut result = ProbeTargetInfo::default(chip);
+    if user_options.get_flag("connect-under-reset") {
+        result.connect_under_reset = true;
+    }
 
-    if let Some(core) = user_options.get_one::<usize>("core") {
-        result.core = *core;
+    if user_options.get_flag("allow-erase-all") {
+        result.allow_erase_all = true;
     }
 
     result
@@ -98,18 +140,52 @@ fn get_known_board(user_options: &ArgMatches) -> Option<Box<dyn KnownBoard>> {
     })
 }
 
+/// Load the app given through `--tab`. This can be a TAB file, a bare TBF
+/// (which needs an architecture), or `-` to read either of them from stdin.
+fn open_tab(path: &str, arch: Option<&str>) -> Result<Tab> {
+    let (data, is_tbf) = if path == "-" {
+        let mut data = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .context("Failed to read app from stdin.")?;
+
+        // TABs are tar archives, so a stream that starts with valid TBF
+        // lengths can only be a bare TBF.
+        let is_tbf = data
+            .get(0..8)
+            .and_then(|lengths| lengths.try_into().ok())
+            .is_some_and(|lengths| parse_tbf_header_lengths(lengths).is_ok());
+        (data, is_tbf)
+    } else if path.ends_with(".tbf") {
+        let data = std::fs::read(path).context("Failed to read the provided TBF file.")?;
+        (data, true)
+    } else {
+        return Ok(Tab::open(path.to_owned())?);
+    };
+
+    if is_tbf {
+        let Some(arch) = arch else {
+            bail!("The architecture of a bare TBF cannot be inferred. Use '--arch' to specify it.");
+        };
+        Ok(Tab::from_tbf(arch, data)?)
+    } else {
+        Ok(Tab::from_bytes(&data)?)
+    }
+}
+
 async fn open_connection(user_options: &ArgMatches) -> Result<TockloaderConnection> {
     if using_serial(user_options) {
-        let path = if let Some(path) = user_options.get_one::<String>("port") {
-            path.clone()
-        } else {
-            let serial_ports = list_serial_ports().context("Failed to list serial ports.")?;
-            let port_names: Vec<_> = serial_ports.iter().map(|p| p.port_name.clone()).collect();
-
-            inquire::Select::new("Which serial port do you want to use?", port_names)
-                .prompt()
-                .context("No device is connected.")?
+        let selector = user_options.get_one::<String>("port").map(String::as_str);
+        let serial_ports = match list_serial_ports() {
+            Ok(ports) => ports,
+            // An explicit port can still be opened by its path.
+            Err(e) if selector.is_some() => {
+                log::debug!("Failed to list serial ports: {e}");
+                vec![]
+            }
+            Err(e) => return Err(e).context("Failed to list serial ports."),
         };
+        let path = select_port(serial_ports, selector)?;
 
         let mut conn: TockloaderConnection =
             SerialConnection::new(path, get_serial_target_info(user_options)).into();
@@ -119,10 +195,10 @@ async fn open_connection(user_options: &ArgMatches) -> Result<TockloaderConnecti
 
         Ok(conn)
     } else {
-        let ans =
-            inquire::Select::new("Which debug probe do you want to use?", list_debug_probes())
-                .prompt()
-                .context("No debug probe is connected.")?;
+        let ans = select_probe(
+            list_debug_probes(),
+            user_options.get_one::<String>("probe").map(String::as_str),
+        )?;
 
         let mut conn: TockloaderConnection =
             ProbeRSConnection::new(ans, get_probe_target_info(user_options)).into();
@@ -215,19 +291,44 @@ async fn main() -> Result<()> {
                 .await
                 .context("Failed to get data from the board.")?;
 
-            display::print_info(&mut attributes.apps, &mut attributes.system).await;
+            let keys = load_public_keys(sub_matches)?;
+            let verifications = conn
+                .verify_apps(&attributes.apps, &keys)
+                .await
+                .context("Failed to check app credentials.")?;
+
+            display::print_info(
+                &mut attributes.apps,
+                &mut attributes.system,
+                &verifications,
+                attributes.bootloader.as_ref(),
+                attributes.board_id.as_deref(),
+            )
+            .await;
         }
         Some(("install", sub_matches)) => {
             cli::validate(&mut cmd, sub_matches);
-            let tab_file = Tab::open(sub_matches.get_one::<String>("tab").unwrap().to_string())
-                .context("Failed to use provided tab file.")?;
+            let settings = get_board_settings(sub_matches);
+            let arch = sub_matches
+                .get_one::<String>("arch")
+                .or(settings.arch.as_ref());
+            let tab_file = open_tab(
+                sub_matches.get_one::<String>("tab").unwrap(),
+                arch.map(String::as_str),
+            )
+            .context("Failed to use provided tab file.")?;
 
             let mut conn = open_connection(sub_matches).await?;
-            let settings = get_board_settings(sub_matches);
 
             conn.install_app(&settings, tab_file)
                 .await
                 .context("Failed to install app.")?;
+
+            if sub_matches.get_flag("reset-after") {
+                conn.reset(ResetMode::Run)
+                    .await
+                    .context("Failed to reset the board.")?;
+            }
         }
         Some(("erase-apps", sub_matches)) => {
             cli::validate(&mut cmd, sub_matches);
@@ -237,6 +338,164 @@ async fn main() -> Result<()> {
             conn.erase_apps(&settings)
                 .await
                 .context("Failed to erase apps.")?;
+
+            if sub_matches.get_flag("reset-after") {
+                conn.reset(ResetMode::Run)
+                    .await
+                    .context("Failed to reset the board.")?;
+            }
+        }
+        Some(("reset", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let mode = if sub_matches.get_flag("halt") {
+                ResetMode::Halt
+            } else {
+                ResetMode::Run
+            };
+            let mut conn = open_connection(sub_matches).await?;
+
+            conn.reset(mode)
+                .await
+                .context("Failed to reset the board.")?;
+        }
+        Some(("get-attribute", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let mut conn = open_connection(sub_matches).await?;
+            let key = sub_matches.get_one::<String>("key").unwrap();
+
+            let value = conn
+                .get_attribute(key)
+                .await
+                .context("Failed to read attribute.")?;
+            println!("{value}");
+        }
+        Some(("set-attribute", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let mut conn = open_connection(sub_matches).await?;
+            let key = sub_matches.get_one::<String>("key").unwrap();
+            let value = sub_matches.get_one::<String>("value").unwrap();
+
+            conn.set_attribute(key, value)
+                .await
+                .context("Failed to set attribute.")?;
+        }
+        Some(("remove-attribute", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let mut conn = open_connection(sub_matches).await?;
+            let key = sub_matches.get_one::<String>("key").unwrap();
+
+            conn.remove_attribute(key)
+                .await
+                .context("Failed to remove attribute.")?;
+        }
+        Some(("flash-bootloader", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let path = sub_matches.get_one::<String>("binary").unwrap();
+            let address = *sub_matches.get_one::<u64>("address").unwrap();
+            let force = sub_matches.get_flag("force");
+            let binary = std::fs::read(path).context("Failed to read bootloader binary.")?;
+
+            let mut conn = open_connection(sub_matches).await?;
+
+            conn.flash_bootloader(&binary, address, force)
+                .await
+                .context("Failed to flash bootloader.")?;
+        }
+        Some(("flash-kernel", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let path = sub_matches.get_one::<String>("kernel").unwrap();
+            let data = std::fs::read(path).context("Failed to read kernel file.")?;
+            let kernel = KernelImage::new(data).context("Failed to load kernel.")?;
+            let force = sub_matches.get_flag("force");
+
+            let mut conn = open_connection(sub_matches).await?;
+            let settings = get_board_settings(sub_matches);
+
+            conn.flash_kernel(&settings, &kernel, force)
+                .await
+                .context("Failed to flash kernel.")?;
+        }
+        Some(("read-external", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let address = *sub_matches.get_one::<u64>("address").unwrap();
+            let length = *sub_matches.get_one::<u64>("length").unwrap();
+            let mut conn = open_connection(sub_matches).await?;
+
+            let data = conn
+                .read_external(address, length as usize)
+                .await
+                .context("Failed to read external flash.")?;
+            match sub_matches.get_one::<String>("output") {
+                Some(path) => std::fs::write(path, &data)
+                    .with_context(|| format!("Failed to write {path}."))?,
+                None => display::print_hex_dump(address, &data),
+            }
+        }
+        Some(("write-external", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let address = *sub_matches.get_one::<u64>("address").unwrap();
+            let path = sub_matches.get_one::<String>("binary").unwrap();
+            let data = std::fs::read(path).context("Failed to read binary.")?;
+
+            let mut conn = open_connection(sub_matches).await?;
+
+            conn.write_external(address, &data)
+                .await
+                .context("Failed to write external flash.")?;
+        }
+        Some(("erase-external", sub_matches)) => {
+            cli::validate(&mut cmd, sub_matches);
+            let address = *sub_matches.get_one::<u64>("address").unwrap();
+            let length = *sub_matches.get_one::<u64>("length").unwrap();
+            let mut conn = open_connection(sub_matches).await?;
+
+            conn.erase_external(address, length)
+                .await
+                .context("Failed to erase external flash.")?;
+        }
+        Some(("inspect-tab", sub_matches)) => {
+            let arch = sub_matches
+                .get_one::<String>("arch")
+                .map_or("unknown", String::as_str);
+            let tab_file = open_tab(sub_matches.get_one::<String>("tab").unwrap(), Some(arch))
+                .context("Failed to use provided tab file.")?;
+
+            let tbf_headers = tab_file
+                .tbf_files()
+                .iter()
+                .map(|tbf| tbf.parse_header())
+                .collect::<Result<Vec<_>, _>>()
+                .context("Failed to parse TBF headers inside the tab file.")?;
+
+            let keys = load_public_keys(sub_matches)?;
+            let credentials = tab_file
+                .tbf_files()
+                .iter()
+                .map(|tbf| verify_tbf(tbf.data(), &keys))
+                .collect::<Result<Vec<_>, _>>()
+                .context("Failed to check app credentials inside the tab file.")?;
+
+            display::print_tab(&tab_file, &tbf_headers, &credentials).await;
+        }
+        Some(("sign", sub_matches)) => {
+            let input = sub_matches.get_one::<String>("input").unwrap();
+            let output = sub_matches.get_one::<String>("output").unwrap_or(input);
+            let key_path = sub_matches.get_one::<String>("key").unwrap();
+            let key = PrivateKey::from_file(key_path)
+                .with_context(|| format!("Failed to load private key {key_path}."))?;
+
+            if input.ends_with(".tbf") {
+                let tbf = std::fs::read(input).context("Failed to read TBF file.")?;
+                let signed = sign_tbf(&tbf, &key).context("Failed to sign app.")?;
+                std::fs::write(output, signed).context("Failed to write signed TBF.")?;
+            } else {
+                let mut tab_file =
+                    Tab::open(input.clone()).context("Failed to use provided tab file.")?;
+                tab_file.sign(&key).context("Failed to sign app.")?;
+                TabBuilder::from(tab_file)
+                    .write_to_file(output)
+                    .context("Failed to write signed tab file.")?;
+            }
         }
         _ => {
             println!("Could not run the provided subcommand.");
@@ -0,0 +1,201 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+use anyhow::{bail, Context, Result};
+use probe_rs::probe::DebugProbeInfo;
+use tokio_serial::{SerialPortInfo, SerialPortType};
+
+/// Pick the debug probe described by `selector` (a serial number, a
+/// `vid:pid` pair or an index in the list), or the only connected probe when
+/// there is no selector.
+pub fn select_probe(probes: Vec<DebugProbeInfo>, selector: Option<&str>) -> Result<DebugProbeInfo> {
+    match selector {
+        Some(selector) => find(
+            probes,
+            selector,
+            |probe, selector| {
+                usb_matches(
+                    selector,
+                    probe.vendor_id,
+                    probe.product_id,
+                    probe.serial_number.as_deref(),
+                )
+            },
+            DebugProbeInfo::to_string,
+        )?
+        .with_context(|| format!("No debug probe matches '{selector}'.")),
+        None => choose(probes, "debug probe", "--probe")?.context("No debug probe is connected."),
+    }
+}
+
+/// Pick the serial port described by `selector`, with the same rules as
+/// [select_probe]. A selector that matches no listed port is used as the path
+/// of the port, so that ports which are not enumerated can still be opened.
+pub fn select_port(ports: Vec<SerialPortInfo>, selector: Option<&str>) -> Result<String> {
+    match selector {
+        Some(selector) => {
+            let port = find(
+                ports,
+                selector,
+                |port, selector| {
+                    port.port_name == selector
+                        || match &port.port_type {
+                            SerialPortType::UsbPort(usb) => usb_matches(
+                                selector,
+                                usb.vid,
+                                usb.pid,
+                                usb.serial_number.as_deref(),
+                            ),
+                            _ => false,
+                        }
+                },
+                |port| port.port_name.clone(),
+            )?;
+            Ok(port.map_or_else(|| selector.to_owned(), |port| port.port_name))
+        }
+        None => {
+            let names = ports.into_iter().map(|port| port.port_name).collect();
+            choose(names, "serial port", "--port")?.context("No device is connected.")
+        }
+    }
+}
+
+/// Whether `selector` is the serial number or the hexadecimal `vid:pid` pair
+/// of a USB device.
+fn usb_matches(selector: &str, vid: u16, pid: u16, serial_number: Option<&str>) -> bool {
+    if serial_number == Some(selector) {
+        return true;
+    }
+    let Some((selector_vid, selector_pid)) = selector.split_once(':') else {
+        return false;
+    };
+    u16::from_str_radix(selector_vid, 16) == Ok(vid)
+        && u16::from_str_radix(selector_pid, 16) == Ok(pid)
+}
+
+/// Find the item that `matches` the selector, falling back to reading the
+/// selector as an index in `items`.
+///
+/// # Returns
+/// - `Ok(None)` if nothing matches.
+/// - `Err(_)` if several items match, listing them with `describe`.
+fn find<T>(
+    mut items: Vec<T>,
+    selector: &str,
+    matches: impl Fn(&T, &str) -> bool,
+    describe: impl Fn(&T) -> String,
+) -> Result<Option<T>> {
+    let matching: Vec<usize> = (0..items.len())
+        .filter(|&i| matches(&items[i], selector))
+        .collect();
+
+    match matching[..] {
+        [i] => Ok(Some(items.swap_remove(i))),
+        [] => match selector.parse::<usize>() {
+            Ok(i) if i < items.len() => Ok(Some(items.swap_remove(i))),
+            _ => Ok(None),
+        },
+        _ => {
+            let candidates = matching
+                .iter()
+                .map(|&i| format!("\n  {}", describe(&items[i])))
+                .collect::<String>();
+            bail!("'{selector}' matches several devices:{candidates}");
+        }
+    }
+}
+
+/// Choose among `items` without a selector: the only item is picked as is,
+/// and the user is prompted when there are several of them. Prompting is
+/// refused when stdin is not a terminal, as in scripts.
+///
+/// # Returns
+/// - `Ok(None)` if `items` is empty.
+fn choose<T: Display>(mut items: Vec<T>, kind: &str, flag: &str) -> Result<Option<T>> {
+    if items.len() <= 1 {
+        return Ok(items.pop());
+    }
+
+    if !std::io::stdin().is_terminal() {
+        let candidates = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| format!("\n  [{i}] {item}"))
+            .collect::<String>();
+        bail!("Several {kind}s are connected, use '{flag}' to choose one:{candidates}");
+    }
+
+    let answer = inquire::Select::new(&format!("Which {kind} do you want to use?"), items)
+        .prompt()
+        .with_context(|| format!("No {kind} was selected."))?;
+    Ok(Some(answer))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ports() -> Vec<String> {
+        vec![
+            "/dev/ttyACM0".into(),
+            "/dev/ttyACM1".into(),
+            "/dev/ttyUSB0".into(),
+        ]
+    }
+
+    #[test]
+    fn usb_ids() {
+        assert!(usb_matches("0483:374b", 0x0483, 0x374B, None));
+        assert!(usb_matches("0483:374B", 0x0483, 0x374B, Some("066D")));
+        assert!(usb_matches("066D", 0x0483, 0x374B, Some("066D")));
+        assert!(!usb_matches("0483:3748", 0x0483, 0x374B, None));
+        assert!(!usb_matches("066D", 0x0483, 0x374B, None));
+        assert!(!usb_matches("0483", 0x0483, 0x374B, None));
+    }
+
+    #[test]
+    fn find_by_name_or_index() {
+        let exact = |port: &String, selector: &str| port == selector;
+
+        assert_eq!(
+            find(ports(), "/dev/ttyUSB0", exact, String::clone)
+                .unwrap()
+                .as_deref(),
+            Some("/dev/ttyUSB0")
+        );
+        assert_eq!(
+            find(ports(), "1", exact, String::clone).unwrap().as_deref(),
+            Some("/dev/ttyACM1")
+        );
+        assert_eq!(find(ports(), "3", exact, String::clone).unwrap(), None);
+        assert_eq!(
+            find(ports(), "/dev/ttyS0", exact, String::clone).unwrap(),
+            None
+        );
+
+        // Matches take precedence over indices.
+        let prefix = |port: &String, selector: &str| port.ends_with(selector);
+        assert_eq!(
+            find(ports(), "0", prefix, String::clone)
+                .unwrap_err()
+                .to_string(),
+            "'0' matches several devices:\n  /dev/ttyACM0\n  /dev/ttyUSB0"
+        );
+    }
+
+    #[test]
+    fn choose_single() {
+        assert_eq!(
+            choose(vec!["/dev/ttyACM0"], "serial port", "--port").unwrap(),
+            Some("/dev/ttyACM0")
+        );
+        assert_eq!(
+            choose(Vec::<String>::new(), "serial port", "--port").unwrap(),
+            None
+        );
+    }
+}
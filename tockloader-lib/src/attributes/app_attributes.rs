@@ -4,23 +4,28 @@
 
 use probe_rs::{Core, MemoryInterface};
 
-use tbf_parser::parse::{parse_tbf_footer, parse_tbf_header, parse_tbf_header_lengths};
-use tbf_parser::types::{TbfFooterV2Credentials, TbfHeader};
+use tbf_parser::parse::{
+    parse_tbf_footers, parse_tbf_header, parse_tbf_header_lengths, parse_tbf_header_raw,
+};
+use tbf_parser::types::{TbfFooterTlv, TbfFooterV2Credentials, TbfHeader, TbfRawTlv};
 use tbf_parser::{self};
-use tokio_serial::SerialStream;
 
-use crate::bootloader_serial::{issue_command, Command, Response};
+use crate::bootloader_serial::BootloaderPort;
 use crate::errors::{TockError, TockloaderError};
+use crate::read_cache::ReadCache;
 
 /// This structure contains all relevant information about a tock application.
 ///
-/// All data is stored either within [TbfHeader]s, or [TbfFooter]s.
+/// All data is stored either within [TbfHeader]s, or [TbfFooter]s. Every TLV
+/// entry of the header, including the ones [TbfHeader] does not keep, is also
+/// available raw in `tbf_tlvs`.
 ///
 /// See also <https://book.tockos.org/doc/tock_binary_format>
 #[derive(Debug)]
 pub struct AppAttributes {
     pub address: u64,
     pub tbf_header: TbfHeader,
+    pub tbf_tlvs: Vec<TbfRawTlv>,
     pub tbf_footers: Vec<TbfFooter>,
 }
 
@@ -45,11 +50,13 @@ impl AppAttributes {
     pub(crate) fn new(
         address: u64,
         header_data: TbfHeader,
+        tlvs: Vec<TbfRawTlv>,
         footers_data: Vec<TbfFooter>,
     ) -> AppAttributes {
         AppAttributes {
             address,
             tbf_header: header_data,
+            tbf_tlvs: tlvs,
             tbf_footers: footers_data,
         }
     }
@@ -114,6 +121,8 @@ impl AppAttributes {
             log::debug!("App #{apps_counter}: Header data: {header_data:?}");
             let header = parse_tbf_header(&header_data, tbf_version)
                 .map_err(TockError::InvalidAppTbfHeader)?;
+            let tlvs =
+                parse_tbf_header_raw(&header_data).map_err(TockError::InvalidAppTbfHeader)?;
 
             // The end of the application binary marks the beginning of the
             // footer.
@@ -131,32 +140,13 @@ impl AppAttributes {
                 }
             };
 
-            let mut footers: Vec<TbfFooter> = vec![];
-            let total_footers_size = total_size - binary_end_offset;
-            let mut footer_offset = binary_end_offset;
-            let mut footer_number = 0;
+            // Footers span from the end of the binary to the end of the
+            // application.
+            let mut footer_data = vec![0u8; (total_size - binary_end_offset) as usize];
+            board_core.read(appaddr + binary_end_offset as u64, &mut footer_data)?;
+            let footers = parse_footers(&footer_data, apps_counter);
 
-            // Try to parse footers until we reach the end of the application.
-            while footer_offset < total_size {
-                // We don't know the size of the current footer, so we read the
-                // remaining bytes in the application (`footer_offset -
-                // binary_end_offset`) , even if we overread.
-                let mut appfooter =
-                    vec![0u8; (total_footers_size - (footer_offset - binary_end_offset)) as usize];
-
-                board_core.read(appaddr + footer_offset as u64, &mut appfooter)?;
-
-                let footer_info =
-                    parse_tbf_footer(&appfooter).map_err(TockError::InvalidAppTbfHeader)?;
-
-                footers.insert(footer_number, TbfFooter::new(footer_info.0, footer_info.1));
-
-                footer_number += 1;
-                // we add 4 because that is the size of TL part of the TLV header (2 bytes type + 2 bytes length)
-                footer_offset += footer_info.1 + 4;
-            }
-
-            let details: AppAttributes = AppAttributes::new(appaddr, header, footers);
+            let details: AppAttributes = AppAttributes::new(appaddr, header, tlvs, footers);
 
             apps_details.insert(apps_counter, details);
             apps_counter += 1;
@@ -178,27 +168,22 @@ impl AppAttributes {
     ///   Board-specific. See also
     ///   [BoardSettings](crate::board_settings::BoardSettings).
     pub(crate) async fn read_apps_data_serial(
-        port: &mut SerialStream,
+        port: &mut BootloaderPort,
         addr: u64,
     ) -> Result<Vec<AppAttributes>, TockloaderError> {
         let mut appaddr: u64 = addr;
         let mut apps_counter = 0;
         let mut apps_details: Vec<AppAttributes> = vec![];
 
+        // Headers and footers are small and close to each other, so they are
+        // served from a few large reads.
+        let mut cache = ReadCache::new(port);
+
         // All applications are stored sequentially in memory, so we read until
         // we fail to parse.
         loop {
-            // The tockloader protocol only supports 32-bit architectures,
-            // though in the future support will be extended to 64-bit.
-            let mut pkt = (appaddr as u32).to_le_bytes().to_vec();
-            let length = (8_u16).to_le_bytes().to_vec();
-            for i in length {
-                pkt.push(i);
-            }
-
             // Read the first 8 bytes, which is the length of a TLV header.
-            let (_, appdata) =
-                issue_command(port, Command::ReadRange, pkt, true, 8, Response::ReadRange).await?;
+            let appdata = cache.read(appaddr, 8).await?;
 
             let tbf_version: u16;
             let header_size: u16;
@@ -226,26 +211,14 @@ impl AppAttributes {
                 "App #{apps_counter}: TBF version {tbf_version}, header size {header_size}, total size {total_size}",
             );
 
-            let mut pkt = (appaddr as u32).to_le_bytes().to_vec();
-            let length = (header_size).to_le_bytes().to_vec();
-            for i in length {
-                pkt.push(i);
-            }
-
             // Read the rest of the header
-            let (_, header_data) = issue_command(
-                port,
-                Command::ReadRange,
-                pkt,
-                true,
-                header_size.into(),
-                Response::ReadRange,
-            )
-            .await?;
+            let header_data = cache.read(appaddr, header_size.into()).await?;
 
             log::debug!("App #{apps_counter}: Header data: {header_data:?}");
             let header = parse_tbf_header(&header_data, tbf_version)
                 .map_err(TockError::InvalidAppTbfHeader)?;
+            let tlvs =
+                parse_tbf_header_raw(&header_data).map_err(TockError::InvalidAppTbfHeader)?;
             let binary_end_offset = header.get_binary_end();
 
             match &header {
@@ -256,46 +229,17 @@ impl AppAttributes {
                 }
             };
 
-            let mut footers: Vec<TbfFooter> = vec![];
-            let total_footers_size = total_size - binary_end_offset;
-            let mut footer_offset = binary_end_offset;
-            let mut footer_number = 0;
-
-            // Try to parse footers until we reach the end of the application.
-            while footer_offset < total_size {
-                // We don't know the size of the current footer, so we read the
-                // remaining bytes in the application (`footer_offset -
-                // binary_end_offset`) , even if we overread.
-                let mut pkt = (appaddr as u32 + footer_offset).to_le_bytes().to_vec();
-                let length = ((total_footers_size - (footer_offset - binary_end_offset)) as u16)
-                    .to_le_bytes()
-                    .to_vec();
-                for i in length {
-                    pkt.push(i);
-                }
-
-                // Read the next header (and perhaps data beyond it)
-                let (_, appfooter) = issue_command(
-                    port,
-                    Command::ReadRange,
-                    pkt,
-                    true,
-                    (total_footers_size - (footer_offset - binary_end_offset)) as usize,
-                    Response::ReadRange,
+            // Footers span from the end of the binary to the end of the
+            // application.
+            let footer_data = cache
+                .read(
+                    appaddr + binary_end_offset as u64,
+                    (total_size - binary_end_offset) as usize,
                 )
                 .await?;
+            let footers = parse_footers(&footer_data, apps_counter);
 
-                let footer_info =
-                    parse_tbf_footer(&appfooter).map_err(TockError::InvalidAppTbfHeader)?;
-
-                footers.insert(footer_number, TbfFooter::new(footer_info.0, footer_info.1));
-
-                footer_number += 1;
-                // we add 4 because that is the size of TL part of the TLV header (2 bytes type + 2 bytes length)
-                footer_offset += footer_info.1 + 4;
-            }
-
-            let details: AppAttributes = AppAttributes::new(appaddr, header, footers);
+            let details: AppAttributes = AppAttributes::new(appaddr, header, tlvs, footers);
 
             apps_details.insert(apps_counter, details);
             apps_counter += 1;
@@ -304,3 +248,34 @@ impl AppAttributes {
         Ok(apps_details)
     }
 }
+
+/// Collect the credentials footers of an application. Footers of unknown types,
+/// footers that cannot be parsed and trailing padding are skipped.
+fn parse_footers(footer_data: &[u8], app_number: usize) -> Vec<TbfFooter> {
+    let mut footers = vec![];
+
+    for footer in parse_tbf_footers(footer_data) {
+        match footer {
+            Ok(TbfFooterTlv::Credentials {
+                length,
+                credentials,
+                ..
+            }) => footers.push(TbfFooter::new(credentials, length.into())),
+            Ok(TbfFooterTlv::Unknown { offset, tipe, .. }) => {
+                log::debug!(
+                    "App #{app_number}: Skipping unknown footer type {tipe} at offset {offset}"
+                );
+            }
+            Ok(TbfFooterTlv::Padding { offset, length }) => {
+                log::debug!(
+                    "App #{app_number}: {length} bytes of footer padding at offset {offset}"
+                );
+            }
+            Err(e) => {
+                log::warn!("App #{app_number}: Skipping invalid footer: {e:?}");
+            }
+        }
+    }
+
+    footers
+}
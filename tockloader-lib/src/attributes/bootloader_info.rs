@@ -0,0 +1,148 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::bootloader_serial::{issue_command, BootloaderPort, Command, Response};
+use crate::errors::{TockError, TockloaderError};
+
+/// Size of the payload of an `Info` response: a length byte followed by up to
+/// 192 bytes of data.
+const INFO_RESPONSE_SIZE: usize = 193;
+
+/// Information the bootloader reports about itself with the `Info` command, a
+/// JSON object such as `{"version":"1.1.3","name":"Tock Bootloader"}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootloaderInfo {
+    /// Fields of the object. Values that are not strings are kept as JSON.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl BootloaderInfo {
+    /// Query the bootloader with the `Info` command.
+    ///
+    /// # Parameters
+    /// - `port`: Serial access, obtained from a
+    ///   [SerialConnection](crate::connection::SerialConnection)
+    ///
+    /// # Returns
+    /// - Ok(result): if the bootloader answered with a JSON object
+    /// - Err(TockError::BootloaderUnknownCommand): if the bootloader does not
+    ///   implement `Info`
+    /// - Err(TockError::InvalidBootloaderInfo): if the answer is not a JSON
+    ///   object
+    pub(crate) async fn read_serial(port: &mut BootloaderPort) -> Result<Self, TockloaderError> {
+        let (_, buf) = issue_command(
+            port,
+            Command::Info,
+            vec![],
+            true,
+            INFO_RESPONSE_SIZE,
+            Response::Info,
+        )
+        .await?;
+
+        Ok(parse_bootloader_info(&buf)?)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+}
+
+/// Query the unique ID of the board with the `ID` command. The bootloader
+/// frames the answer like an `Info` response: a length byte followed by the
+/// ID.
+///
+/// # Returns
+/// - Ok(id): the raw bytes of the ID
+/// - Err(TockError::BootloaderUnknownCommand): if the bootloader does not
+///   implement `ID`
+pub(crate) async fn read_board_id_serial(
+    port: &mut BootloaderPort,
+) -> Result<Vec<u8>, TockloaderError> {
+    let (_, buf) = issue_command(
+        port,
+        Command::ID,
+        vec![],
+        true,
+        INFO_RESPONSE_SIZE,
+        Response::Info,
+    )
+    .await?;
+
+    Ok(length_prefixed(&buf).to_vec())
+}
+
+/// The data of a length-prefixed response, cut to the payload if the length
+/// byte claims more than was received.
+fn length_prefixed(data: &[u8]) -> &[u8] {
+    let Some((&length, rest)) = data.split_first() else {
+        return &[];
+    };
+    &rest[..rest.len().min(length as usize)]
+}
+
+/// Parse the payload of an `Info` response.
+pub(crate) fn parse_bootloader_info(data: &[u8]) -> Result<BootloaderInfo, TockError> {
+    let json = std::str::from_utf8(length_prefixed(data))
+        .map_err(|e| TockError::InvalidBootloaderInfo(e.to_string()))?;
+    let Value::Object(object) =
+        serde_json::from_str(json).map_err(|e| TockError::InvalidBootloaderInfo(e.to_string()))?
+    else {
+        return Err(TockError::InvalidBootloaderInfo(format!(
+            "expected a JSON object, got {json}"
+        )));
+    };
+
+    let fields = object
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => (key, value),
+            value => (key, value.to_string()),
+        })
+        .collect();
+    Ok(BootloaderInfo { fields })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![payload.len() as u8];
+        data.extend_from_slice(payload);
+        data.resize(INFO_RESPONSE_SIZE, 0);
+        data
+    }
+
+    #[test]
+    fn info_object() {
+        let data = response(br#"{"version":"1.1.3","name":"Tock Bootloader","flags":3}"#);
+        let info = parse_bootloader_info(&data).unwrap();
+
+        assert_eq!(info.get("version"), Some("1.1.3"));
+        assert_eq!(info.get("name"), Some("Tock Bootloader"));
+        assert_eq!(info.get("flags"), Some("3"));
+        assert_eq!(info.fields.len(), 3);
+    }
+
+    #[test]
+    fn invalid_info() {
+        assert!(parse_bootloader_info(&response(b"[1, 2]")).is_err());
+        assert!(parse_bootloader_info(&response(b"{\"version\":")).is_err());
+        assert!(parse_bootloader_info(&response(&[0xFF, 0xFE])).is_err());
+        assert!(parse_bootloader_info(&[]).is_err());
+    }
+
+    #[test]
+    fn length_prefix() {
+        assert_eq!(length_prefixed(&[3, 1, 2, 3, 4]), &[1, 2, 3]);
+        // Longer than the payload.
+        assert_eq!(length_prefixed(&[200, 1, 2]), &[1, 2]);
+        assert_eq!(length_prefixed(&[]), &[] as &[u8]);
+    }
+}
@@ -0,0 +1,150 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::cmp::Ordering;
+
+use crate::errors::TockError;
+
+/// Address of the bootloader version string, stored by the bootloader in its
+/// flags.
+pub(crate) const BOOTLOADER_VERSION_ADDRESS: u64 = 0x40E;
+
+/// Maximum length of the bootloader version string.
+pub(crate) const BOOTLOADER_VERSION_SIZE: usize = 8;
+
+/// Decode the bootloader version string, as read from
+/// [BOOTLOADER_VERSION_ADDRESS].
+///
+/// # Returns
+/// - `None` if the string is empty (erased or zeroed flash) or not UTF-8.
+/// - `Some(_)` otherwise, without the null or erased padding bytes.
+pub(crate) fn decode_bootloader_version(data: &[u8]) -> Option<String> {
+    let end = data
+        .iter()
+        .position(|byte| *byte == 0x00 || *byte == 0xFF)
+        .unwrap_or(data.len());
+    let version = std::str::from_utf8(&data[..end]).ok()?;
+    (!version.is_empty()).then(|| version.to_owned())
+}
+
+/// Read the version of a bootloader binary that will be written at `address`.
+///
+/// # Returns
+/// - `None` if the binary does not cover the version string or holds no
+///   valid version.
+pub(crate) fn binary_bootloader_version(binary: &[u8], address: u64) -> Option<String> {
+    let offset = usize::try_from(BOOTLOADER_VERSION_ADDRESS.checked_sub(address)?).ok()?;
+    let data = binary.get(offset..offset.checked_add(BOOTLOADER_VERSION_SIZE)?)?;
+    decode_bootloader_version(data)
+}
+
+/// Compare two dotted version strings, such as `1.1.3`, component by
+/// component. A leading `v` is ignored.
+///
+/// # Returns
+/// - `None` if either version has a non-numeric component.
+pub(crate) fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    fn components(version: &str) -> Option<Vec<u32>> {
+        version
+            .trim_start_matches('v')
+            .split('.')
+            .map(|component| component.parse().ok())
+            .collect()
+    }
+
+    let mut a = components(a)?;
+    let mut b = components(b)?;
+    // 1.1 and 1.1.0 are the same version.
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    Some(a.cmp(&b))
+}
+
+/// Check that replacing the `current` bootloader with the `new` one is not a
+/// downgrade. Unknown versions cannot be compared, so they are allowed with a
+/// warning.
+///
+/// # Returns
+/// - Err(TockError::BootloaderDowngrade): if `new` is older than `current`
+///   and `force` is not set
+pub(crate) fn check_bootloader_upgrade(
+    current: Option<&str>,
+    new: Option<&str>,
+    force: bool,
+) -> Result<(), TockError> {
+    let (Some(current), Some(new)) = (current, new) else {
+        log::warn!(
+            "Cannot compare bootloader versions (current: {}, new: {})",
+            current.unwrap_or("unknown"),
+            new.unwrap_or("unknown")
+        );
+        return Ok(());
+    };
+
+    match compare_versions(current, new) {
+        Some(Ordering::Greater) if force => {
+            log::warn!("Downgrading bootloader from {current} to {new}");
+            Ok(())
+        }
+        Some(Ordering::Greater) => Err(TockError::BootloaderDowngrade {
+            current: current.to_owned(),
+            new: new.to_owned(),
+        }),
+        Some(_) => Ok(()),
+        None => {
+            log::warn!("Cannot compare bootloader versions {current} and {new}");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        assert_eq!(
+            decode_bootloader_version(b"1.1.3\0\0\0").as_deref(),
+            Some("1.1.3")
+        );
+        assert_eq!(
+            decode_bootloader_version(b"1.10.0\xFF\xFF").as_deref(),
+            Some("1.10.0")
+        );
+        assert_eq!(decode_bootloader_version(&[0xFF; 8]), None);
+        assert_eq!(decode_bootloader_version(&[0x00; 8]), None);
+
+        let mut binary = vec![0; 0x500];
+        binary[0x40E..0x413].copy_from_slice(b"1.2.0");
+        assert_eq!(
+            binary_bootloader_version(&binary, 0).as_deref(),
+            Some("1.2.0")
+        );
+        assert_eq!(binary_bootloader_version(&binary[..0x410], 0), None);
+        assert_eq!(binary_bootloader_version(&binary, 0x1000), None);
+    }
+
+    #[test]
+    fn compare() {
+        assert_eq!(compare_versions("1.1.3", "1.1.3"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("1.1.3", "1.2.0"), Some(Ordering::Less));
+        assert_eq!(compare_versions("1.10.0", "1.9.9"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("v1.1", "1.1.0"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("1.1.3", "dev"), None);
+    }
+
+    #[test]
+    fn downgrade() {
+        assert!(check_bootloader_upgrade(Some("1.1.3"), Some("1.2.0"), false).is_ok());
+        assert!(check_bootloader_upgrade(Some("1.1.3"), Some("1.1.3"), false).is_ok());
+        assert!(matches!(
+            check_bootloader_upgrade(Some("1.2.0"), Some("1.1.3"), false),
+            Err(TockError::BootloaderDowngrade { .. })
+        ));
+        assert!(check_bootloader_upgrade(Some("1.2.0"), Some("1.1.3"), true).is_ok());
+        assert!(check_bootloader_upgrade(None, Some("1.1.3"), false).is_ok());
+    }
+}
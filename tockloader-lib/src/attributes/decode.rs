@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
+use crate::errors::TockError;
+
 /// Attributes are key-value pairs that describe hardware configuration, stored
 /// in a fixed 64-byte format:
 ///
@@ -25,6 +27,27 @@ impl DecodedAttribute {
     }
 }
 
+/// Address of the first attribute slot. Attributes are stored by the
+/// bootloader from 0x600 up to 0x9FF. See:
+/// <https://book.tockos.org/doc/memory_layout#flash-1>
+pub(crate) const ATTRIBUTES_ADDRESS: u64 = 0x600;
+
+/// Size of a single attribute slot.
+pub(crate) const ATTRIBUTE_SIZE: usize = 64;
+
+/// Number of attribute slots.
+pub(crate) const ATTRIBUTE_SLOTS: usize = 16;
+
+/// Contents written to a slot to remove its attribute. A zero value length
+/// marks the slot as empty.
+pub(crate) const EMPTY_ATTRIBUTE: [u8; ATTRIBUTE_SIZE] = [0; ATTRIBUTE_SIZE];
+
+/// Maximum length of an attribute key, in bytes.
+pub(crate) const ATTRIBUTE_KEY_SIZE: usize = 8;
+
+/// Maximum length of an attribute value, in bytes.
+pub(crate) const ATTRIBUTE_VALUE_SIZE: usize = 55;
+
 /// Internal function used to decode the raw data into a [DecodedAttribute].
 ///
 /// # Params
@@ -35,48 +58,186 @@ impl DecodedAttribute {
 ///   data).
 /// - `Some(_)` otherwise
 pub(crate) fn decode_attribute(step: &[u8]) -> Option<DecodedAttribute> {
-    let raw_key = &step[0..8];
-
-    let decoder_key = utf8_decode::Decoder::new(raw_key.iter().cloned());
+    let key = std::str::from_utf8(step.get(0..ATTRIBUTE_KEY_SIZE)?).ok()?;
+    let key = key.trim_end_matches('\0').to_string();
 
-    let mut key = String::new();
-    for n in decoder_key {
-        key.push(n.expect("Error getting key for attributes."));
+    let vlen = *step.get(8)?;
+    if vlen as usize > ATTRIBUTE_VALUE_SIZE || vlen == 0 {
+        return None;
     }
+    let value = std::str::from_utf8(step.get(9..(9 + vlen as usize))?).ok()?;
+    let value = value.trim_end_matches('\0').to_string();
+
+    Some(DecodedAttribute::new(key, value))
+}
+
+/// Decode every valid attribute of the attributes region, as read from
+/// [ATTRIBUTES_ADDRESS]. Empty and invalid slots are skipped.
+///
+/// # Returns
+/// The slot index and the attribute, for each valid slot.
+pub(crate) fn decode_attributes(data: &[u8]) -> Vec<(usize, DecodedAttribute)> {
+    data.chunks_exact(ATTRIBUTE_SIZE)
+        .take(ATTRIBUTE_SLOTS)
+        .enumerate()
+        .filter_map(|(slot, slot_data)| Some((slot, decode_attribute(slot_data)?)))
+        .collect()
+}
 
-    key = key.trim_end_matches('\0').to_string();
-    let vlen = step[8];
+/// Find the value of the attribute with the given key.
+pub(crate) fn find_attribute<'a>(
+    attributes: &'a [(usize, DecodedAttribute)],
+    key: &str,
+) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(_, attribute)| attribute.key == key)
+        .map(|(_, attribute)| attribute.value.as_str())
+}
 
-    if vlen > 55 || vlen == 0 {
-        return None;
+/// Encode an attribute into the 64-byte slot format read by
+/// [decode_attribute].
+///
+/// # Returns
+/// - `Err(_)` if the key is empty or longer than 8 bytes, or if the value is
+///   empty or longer than 55 bytes.
+/// - `Ok(_)` otherwise
+pub(crate) fn encode_attribute(key: &str, value: &str) -> Result<[u8; ATTRIBUTE_SIZE], TockError> {
+    if key.is_empty() || key.len() > ATTRIBUTE_KEY_SIZE {
+        return Err(TockError::InvalidAttribute(format!(
+            "key '{key}' must be between 1 and {ATTRIBUTE_KEY_SIZE} bytes long"
+        )));
+    }
+    if value.is_empty() || value.len() > ATTRIBUTE_VALUE_SIZE {
+        return Err(TockError::InvalidAttribute(format!(
+            "value of '{key}' must be between 1 and {ATTRIBUTE_VALUE_SIZE} bytes long"
+        )));
     }
-    let raw_value = &step[9..(9 + vlen as usize)];
-    let decoder_value = utf8_decode::Decoder::new(raw_value.iter().cloned());
 
-    let mut value = String::new();
+    let mut slot = [0u8; ATTRIBUTE_SIZE];
+    slot[..key.len()].copy_from_slice(key.as_bytes());
+    slot[ATTRIBUTE_KEY_SIZE] = value.len() as u8;
+    slot[ATTRIBUTE_KEY_SIZE + 1..ATTRIBUTE_KEY_SIZE + 1 + value.len()]
+        .copy_from_slice(value.as_bytes());
+    Ok(slot)
+}
 
-    for n in decoder_value {
-        value.push(n.expect("Error getting key for attributes."));
+/// Choose the slot where the attribute with the given key should be written:
+/// the slot already holding it, or else the first slot without a valid
+/// attribute.
+pub(crate) fn slot_for_attribute(
+    attributes: &[(usize, DecodedAttribute)],
+    key: &str,
+) -> Result<usize, TockError> {
+    if let Some((slot, _)) = attributes
+        .iter()
+        .find(|(_, attribute)| attribute.key == key)
+    {
+        return Ok(*slot);
     }
+    (0..ATTRIBUTE_SLOTS)
+        .find(|slot| attributes.iter().all(|(used, _)| used != slot))
+        .ok_or(TockError::AttributeSlotsFull)
+}
 
-    value = value.trim_end_matches('\0').to_string();
-    Some(DecodedAttribute::new(key, value))
+/// Find the slot holding the attribute with the given key.
+pub(crate) fn find_attribute_slot(
+    attributes: &[(usize, DecodedAttribute)],
+    key: &str,
+) -> Result<usize, TockError> {
+    attributes
+        .iter()
+        .find(|(_, attribute)| attribute.key == key)
+        .map(|(slot, _)| *slot)
+        .ok_or_else(|| TockError::MissingAttribute(key.to_string()))
 }
 
-// TODO(george-cosma) replace this function with std::str::from_utf8(...). It
-// does the same thing.
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
 
-/// Transform a byte-slice into a String.
-///
-/// # Panics
-///
-/// This code panics if the given bytes are not utf-8 representable
-pub(crate) fn bytes_to_string(raw: &[u8]) -> String {
-    let decoder = utf8_decode::Decoder::new(raw.iter().cloned());
+    /// Encode an attribute slot the way the bootloader stores it.
+    pub(crate) fn attribute_slot(key: &str, value: &str) -> [u8; ATTRIBUTE_SIZE] {
+        encode_attribute(key, value).unwrap()
+    }
+
+    /// Attributes region of a board with a bootloader, with the slots in a
+    /// different order than the one used by the bootloader, an erased slot
+    /// and an invalid one.
+    pub(crate) fn attributes_fixture() -> Vec<u8> {
+        let mut data = vec![];
+        data.extend_from_slice(&attribute_slot("arch", "cortex-m4"));
+        data.extend_from_slice(&[0xFF; ATTRIBUTE_SIZE]);
+        data.extend_from_slice(&attribute_slot("board", "nrf52dk"));
+        data.extend_from_slice(&attribute_slot("appaddr", "0x40000"));
+        let mut invalid = attribute_slot("boothash", "abc");
+        invalid[9] = 0xC3;
+        data.extend_from_slice(&invalid);
+        data.resize(ATTRIBUTE_SIZE * ATTRIBUTE_SLOTS, 0xFF);
+        data
+    }
+
+    #[test]
+    fn decode_by_key() {
+        let attributes = decode_attributes(&attributes_fixture());
+
+        let slots: Vec<usize> = attributes.iter().map(|(slot, _)| *slot).collect();
+        assert_eq!(slots, vec![0, 2, 3]);
+        assert_eq!(find_attribute(&attributes, "board"), Some("nrf52dk"));
+        assert_eq!(find_attribute(&attributes, "arch"), Some("cortex-m4"));
+        assert_eq!(find_attribute(&attributes, "appaddr"), Some("0x40000"));
+        assert_eq!(find_attribute(&attributes, "boothash"), None);
+    }
+
+    #[test]
+    fn value_lengths() {
+        let full = "v".repeat(55);
+        assert_eq!(
+            decode_attribute(&attribute_slot("key", &full))
+                .unwrap()
+                .value,
+            full
+        );
+
+        let mut slot = attribute_slot("key", "value");
+        slot[8] = 56;
+        assert!(decode_attribute(&slot).is_none());
+        slot[8] = 0;
+        assert!(decode_attribute(&slot).is_none());
+        assert!(decode_attribute(&slot[..8]).is_none());
+    }
+
+    #[test]
+    fn encode_limits() {
+        let slot = encode_attribute("appaddr", "0x40000").unwrap();
+        let decoded = decode_attribute(&slot).unwrap();
+        assert_eq!(decoded.key, "appaddr");
+        assert_eq!(decoded.value, "0x40000");
+
+        assert!(encode_attribute("8bytekey", &"v".repeat(55)).is_ok());
+        assert!(encode_attribute("ninebytes", "value").is_err());
+        assert!(encode_attribute("", "value").is_err());
+        assert!(encode_attribute("key", &"v".repeat(56)).is_err());
+        assert!(encode_attribute("key", "").is_err());
+    }
+
+    #[test]
+    fn slot_selection() {
+        let attributes = decode_attributes(&attributes_fixture());
+
+        // Existing keys are overwritten in place.
+        assert_eq!(slot_for_attribute(&attributes, "board").unwrap(), 2);
+        // New keys take the first erased or invalid slot.
+        assert_eq!(slot_for_attribute(&attributes, "jldevice").unwrap(), 1);
+        assert_eq!(find_attribute_slot(&attributes, "appaddr").unwrap(), 3);
+        assert!(find_attribute_slot(&attributes, "jldevice").is_err());
 
-    let mut string = String::new();
-    for n in decoder {
-        string.push(n.expect("Error getting key for attributes."));
+        let full: Vec<u8> = (0..ATTRIBUTE_SLOTS)
+            .flat_map(|slot| attribute_slot(&format!("key{slot}"), "value"))
+            .collect();
+        assert!(matches!(
+            slot_for_attribute(&decode_attributes(&full), "board"),
+            Err(TockError::AttributeSlotsFull)
+        ));
     }
-    string
 }
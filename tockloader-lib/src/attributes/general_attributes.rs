@@ -3,12 +3,18 @@
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
 use super::app_attributes::AppAttributes;
+use super::bootloader_info::BootloaderInfo;
 use super::system_attributes::SystemAttributes;
 
 #[derive(Debug)]
 pub struct GeneralAttributes {
     pub system: SystemAttributes,
     pub apps: Vec<AppAttributes>,
+    /// Answer to the bootloader `Info` command, if the bootloader was reached
+    /// over serial and implements it.
+    pub bootloader: Option<BootloaderInfo>,
+    /// Unique ID of the board, from the bootloader `ID` command.
+    pub board_id: Option<Vec<u8>>,
 }
 
 impl GeneralAttributes {
@@ -19,6 +25,8 @@ impl GeneralAttributes {
         GeneralAttributes {
             system: system_attributes,
             apps: apps_attributes,
+            bootloader: None,
+            board_id: None,
         }
     }
 }
@@ -0,0 +1,163 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+/// Number of bytes before the start of applications reserved for the kernel
+/// attributes.
+pub(crate) const KERNEL_ATTRIBUTES_SIZE: usize = 100;
+
+const SENTINEL: &[u8; 4] = b"TOCK";
+
+/// TLV type of the memory region reserved for applications.
+const TLV_APP_MEMORY: u16 = 0x0101;
+/// TLV type of the flash region holding the kernel binary.
+const TLV_KERNEL_BINARY: u16 = 0x0102;
+
+/// Attributes the kernel stores at the end of its flash region, right before
+/// the start of applications.
+///
+/// The layout is read backwards from the start of applications:
+///
+/// 1. 4 bytes: sentinel, the ASCII string "TOCK"
+/// 2. 1 byte: version of the layout
+/// 3. 3 bytes: reserved
+/// 4. TLVs, each made of the value followed by a 2 byte type and a 2 byte
+///    length, so that they can be parsed from their end.
+///
+/// See also <https://book.tockos.org/doc/kernel_attributes.html>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KernelAttributes {
+    pub version: u8,
+    /// Start address and length of the RAM reserved for applications.
+    pub app_memory: Option<(u32, u32)>,
+    /// Start address and length of the kernel binary in flash.
+    pub kernel_binary: Option<(u32, u32)>,
+}
+
+/// Parse the kernel attributes that end at the end of `data`, which is usually
+/// the [KERNEL_ATTRIBUTES_SIZE] bytes before the start of applications.
+///
+/// # Returns
+/// - `None` if `data` does not end with the sentinel, which means that the
+///   kernel does not store attributes.
+/// - `Some(_)` otherwise. TLVs are only parsed for version 1 of the layout,
+///   unknown TLVs are skipped.
+pub(crate) fn parse_kernel_attributes(data: &[u8]) -> Option<KernelAttributes> {
+    let header_start = data.len().checked_sub(8)?;
+    if &data[header_start + 4..] != SENTINEL {
+        return None;
+    }
+
+    let mut attributes = KernelAttributes {
+        version: data[header_start + 3],
+        ..Default::default()
+    };
+    if attributes.version != 1 {
+        log::debug!(
+            "Unknown kernel attributes version {}, skipping TLVs",
+            attributes.version
+        );
+        return Some(attributes);
+    }
+
+    let mut end = header_start;
+    while end >= 4 {
+        let tipe = u16::from_le_bytes([data[end - 4], data[end - 3]]);
+        let length = u16::from_le_bytes([data[end - 2], data[end - 1]]) as usize;
+        let Some(start) = (end - 4).checked_sub(length) else {
+            log::debug!("Kernel attributes TLV {tipe:#x} runs past the reserved space");
+            break;
+        };
+        let value = &data[start..end - 4];
+
+        match (tipe, value.len()) {
+            (TLV_APP_MEMORY, 8) => attributes.app_memory = Some(read_region(value)),
+            (TLV_KERNEL_BINARY, 8) => attributes.kernel_binary = Some(read_region(value)),
+            _ => log::debug!("Skipping kernel attributes TLV {tipe:#x} of length {length}"),
+        }
+
+        end = start;
+    }
+
+    Some(attributes)
+}
+
+/// Read the start address and length of a memory region TLV.
+fn read_region(value: &[u8]) -> (u32, u32) {
+    (
+        u32::from_le_bytes(value[0..4].try_into().expect("value is 8 bytes long")),
+        u32::from_le_bytes(value[4..8].try_into().expect("value is 8 bytes long")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kernel attributes as written by a Tock 2.x kernel: kernel binary and
+    /// app memory TLVs, reserved bytes, version 1 and the sentinel.
+    fn fixture() -> Vec<u8> {
+        let mut data = vec![0xFF; 68];
+        // Kernel binary: start 0x0, length 0x24000.
+        data.extend_from_slice(&0x0u32.to_le_bytes());
+        data.extend_from_slice(&0x24000u32.to_le_bytes());
+        data.extend_from_slice(&TLV_KERNEL_BINARY.to_le_bytes());
+        data.extend_from_slice(&8u16.to_le_bytes());
+        // App memory: start 0x20004000, length 0x3C000.
+        data.extend_from_slice(&0x2000_4000u32.to_le_bytes());
+        data.extend_from_slice(&0x3C000u32.to_le_bytes());
+        data.extend_from_slice(&TLV_APP_MEMORY.to_le_bytes());
+        data.extend_from_slice(&8u16.to_le_bytes());
+        // Reserved, version and sentinel.
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(SENTINEL);
+        assert_eq!(data.len(), KERNEL_ATTRIBUTES_SIZE);
+        data
+    }
+
+    #[test]
+    fn tock_kernel() {
+        assert_eq!(
+            parse_kernel_attributes(&fixture()),
+            Some(KernelAttributes {
+                version: 1,
+                app_memory: Some((0x2000_4000, 0x3C000)),
+                kernel_binary: Some((0x0, 0x24000)),
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_tlvs_are_skipped() {
+        let mut data = fixture();
+        // Replace the kernel binary type, keeping its length.
+        data[76..78].copy_from_slice(&0x0201u16.to_le_bytes());
+
+        let attributes = parse_kernel_attributes(&data).unwrap();
+        assert_eq!(attributes.app_memory, Some((0x2000_4000, 0x3C000)));
+        assert_eq!(attributes.kernel_binary, None);
+    }
+
+    #[test]
+    fn missing_sentinel() {
+        assert_eq!(
+            parse_kernel_attributes(&[0xFF; KERNEL_ATTRIBUTES_SIZE]),
+            None
+        );
+        assert_eq!(parse_kernel_attributes(b"TOCK"), None);
+    }
+
+    #[test]
+    fn unknown_version() {
+        let mut data = fixture();
+        data[95] = 2;
+
+        assert_eq!(
+            parse_kernel_attributes(&data),
+            Some(KernelAttributes {
+                version: 2,
+                ..Default::default()
+            })
+        );
+    }
+}
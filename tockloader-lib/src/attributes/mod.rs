@@ -3,6 +3,9 @@
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
 pub mod app_attributes;
+pub mod bootloader_info;
+pub mod bootloader_version;
 pub mod decode;
 pub mod general_attributes;
+pub mod kernel_attributes;
 pub mod system_attributes;
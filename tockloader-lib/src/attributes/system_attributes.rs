@@ -2,14 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
-use byteorder::{ByteOrder, LittleEndian};
 use probe_rs::{Core, MemoryInterface};
-use tokio_serial::SerialStream;
 
-use crate::bootloader_serial::{issue_command, Command, Response};
+use crate::bootloader_serial::BootloaderPort;
 use crate::errors::{AttributeParseError, TockError, TockloaderError};
+use crate::read_cache::ReadRange;
 
-use super::decode::{bytes_to_string, decode_attribute};
+use super::bootloader_version::{
+    decode_bootloader_version, BOOTLOADER_VERSION_ADDRESS, BOOTLOADER_VERSION_SIZE,
+};
+use super::decode::{
+    decode_attributes, find_attribute, ATTRIBUTES_ADDRESS, ATTRIBUTE_SIZE, ATTRIBUTE_SLOTS,
+};
+use super::kernel_attributes::{parse_kernel_attributes, KERNEL_ATTRIBUTES_SIZE};
 
 /// This structure contains all relevant information about board that is stored
 /// in the bootloader ROM.
@@ -50,6 +55,66 @@ impl SystemAttributes {
         }
     }
 
+    /// Build the system attributes from the attributes region (see
+    /// [decode_attributes]) and the bootloader version (see
+    /// [decode_bootloader_version]). Attributes are matched by key, so their
+    /// slot does not matter.
+    ///
+    /// # Returns
+    /// - Err(TockError::AttributeParsing): if `appaddr` is not a hex number
+    pub(crate) fn from_attributes(
+        attributes: &[u8],
+        bootloader_version: &[u8],
+    ) -> Result<Self, TockloaderError> {
+        let mut result = SystemAttributes::new();
+        let attributes = decode_attributes(attributes);
+
+        result.board = find_attribute(&attributes, "board").map(str::to_owned);
+        result.arch = find_attribute(&attributes, "arch").map(str::to_owned);
+        result.boothash = find_attribute(&attributes, "boothash").map(str::to_owned);
+        result.appaddr = find_attribute(&attributes, "appaddr")
+            .map(|appaddr| {
+                // Parse hex string like "0x40000" into actual u64 value
+                u64::from_str_radix(appaddr.trim_start_matches("0x"), 16)
+                    .map_err(|e| TockError::AttributeParsing(AttributeParseError::InvalidNumber(e)))
+            })
+            .transpose()?;
+
+        result.bootloader_version = decode_bootloader_version(bootloader_version);
+
+        Ok(result)
+    }
+
+    /// Address of the kernel attributes, which are stored in the
+    /// [KERNEL_ATTRIBUTES_SIZE] bytes prior to the application start address.
+    fn kernel_attributes_address(&self) -> Result<u64, TockloaderError> {
+        let appaddr = self
+            .appaddr
+            .ok_or(TockError::MissingAttribute("appaddr".to_owned()))?;
+        Ok(appaddr.saturating_sub(KERNEL_ATTRIBUTES_SIZE as u64))
+    }
+
+    /// Fill in the kernel attributes from the [KERNEL_ATTRIBUTES_SIZE] bytes
+    /// prior to the application start address. Fields stay `None` if the
+    /// kernel does not store attributes.
+    pub(crate) fn set_kernel_attributes(&mut self, data: &[u8]) {
+        let Some(kernel_attributes) = parse_kernel_attributes(data) else {
+            log::info!("No kernel attributes found before the start of applications.");
+            return;
+        };
+
+        self.sentinel = Some("TOCK".to_owned());
+        self.kernel_version = Some(kernel_attributes.version.into());
+        if let Some((start, len)) = kernel_attributes.app_memory {
+            self.app_mem_start = Some(start);
+            self.app_mem_len = Some(len);
+        }
+        if let Some((start, len)) = kernel_attributes.kernel_binary {
+            self.kernel_bin_start = Some(start);
+            self.kernel_bin_len = Some(len);
+        }
+    }
+
     /// Read system attributes using a probe-rs connection. A bootloader must be
     /// present on this board for this function to work properly.
     ///
@@ -59,106 +124,23 @@ impl SystemAttributes {
     ///
     /// # Returns
     /// - Ok(result): if attributes were read successfully
-    /// - Err(TockloaderError::MisconfiguredBoard): if no start address is found or valid
-    /// - Err(TockloaderError::MisconfiguredBoard): if attributes don't follow the UTF-8 format
+    /// - Err(TockError::MissingAttribute): if no start address is found
+    /// - Err(TockError::AttributeParsing): if attributes are not valid
     /// - Err(TockloaderError::ProbeRsReadError): if reading fails
     pub(crate) fn read_system_attributes_probe(
         board_core: &mut Core,
     ) -> Result<Self, TockloaderError> {
-        let mut result = SystemAttributes::new();
-        // System attributes start at 0x600 and up to 0x9FF. See:
-        // https://book.tockos.org/doc/memory_layout#flash-1
-        let address = 0x600;
-        // Each attribute is 64 bytes exactly, and there are 16 slots
-        let mut buf = [0u8; 64 * 16];
-
-        board_core.read(address, &mut buf)?;
-
-        let mut data = buf.chunks(64);
-
-        for current_slot in 0..data.len() {
-            let slot_data = match data.next() {
-                Some(data) => data,
-                None => break,
-            };
+        let mut attributes = [0u8; ATTRIBUTE_SIZE * ATTRIBUTE_SLOTS];
+        board_core.read(ATTRIBUTES_ADDRESS, &mut attributes)?;
 
-            // If the attribute chunk was successfully decoded, assign its value
-            // to the corresponding field in `result` based on the index:
-            // - 0 = board name,
-            // - 1 = architecture,
-            // - 2 = application start address (parsed from hex string),
-            // - 3 = boot hash, _ = invalid or missing data is skipped.
-            // NOTE: this can also be done by looping directly through the key attributes.
-            if let Some(decoded_attributes) = decode_attribute(slot_data) {
-                match current_slot {
-                    0 => {
-                        result.board = Some(decoded_attributes.value.to_string());
-                    }
-                    1 => {
-                        result.arch = Some(decoded_attributes.value.to_string());
-                    }
-                    2 => {
-                        // Parse hex string like "0x40000" into actual u64 value
-                        result.appaddr = Some(
-                            u64::from_str_radix(
-                                decoded_attributes
-                                    .value
-                                    .to_string()
-                                    .trim_start_matches("0x"),
-                                16,
-                            )
-                            .map_err(|e| {
-                                TockError::AttributeParsing(AttributeParseError::InvalidNumber(e))
-                            })?,
-                        );
-                    }
-                    3 => {
-                        result.boothash = Some(decoded_attributes.value.to_string());
-                    }
-                    _ => {}
-                }
-            } else {
-                continue;
-            }
-        }
-
-        // TODO(george-cosma): separate kernel attributes from kernel flags.
-
-        let address = 0x40E;
-        let mut buf = [0u8; 8];
-
-        board_core.read_8(address, &mut buf)?;
+        let mut bootloader_version = [0u8; BOOTLOADER_VERSION_SIZE];
+        board_core.read_8(BOOTLOADER_VERSION_ADDRESS, &mut bootloader_version)?;
 
-        let string = String::from_utf8(buf.to_vec())
-            .map_err(|e| TockError::AttributeParsing(AttributeParseError::InvalidString(e)))?;
+        let mut result = SystemAttributes::from_attributes(&attributes, &bootloader_version)?;
 
-        let string = string.trim_matches(char::from(0));
-
-        result.bootloader_version = Some(string.to_owned());
-
-        // The 100 bytes prior to the application start address are reserved for the kernel attributes and flags
-        let mut kernel_attr_binary = [0u8; 100];
-        let kernel_attr_addr = result
-            .appaddr
-            .ok_or(TockError::MissingAttribute("appaddr".to_owned()))?
-            - 100;
-        board_core.read(kernel_attr_addr, &mut kernel_attr_binary)?;
-
-        let sentinel = bytes_to_string(&kernel_attr_binary[96..100]);
-        let kernel_version = LittleEndian::read_uint(&kernel_attr_binary[95..96], 1);
-
-        let app_memory_len = LittleEndian::read_u32(&kernel_attr_binary[84..92]);
-        let app_memory_start = LittleEndian::read_u32(&kernel_attr_binary[80..84]);
-
-        let kernel_binary_start = LittleEndian::read_u32(&kernel_attr_binary[68..72]);
-        let kernel_binary_len = LittleEndian::read_u32(&kernel_attr_binary[72..76]);
-
-        result.sentinel = Some(sentinel);
-        result.kernel_version = Some(kernel_version);
-        result.app_mem_start = Some(app_memory_start);
-        result.app_mem_len = Some(app_memory_len);
-        result.kernel_bin_start = Some(kernel_binary_start);
-        result.kernel_bin_len = Some(kernel_binary_len);
+        let mut kernel_attributes = [0u8; KERNEL_ATTRIBUTES_SIZE];
+        board_core.read(result.kernel_attributes_address()?, &mut kernel_attributes)?;
+        result.set_kernel_attributes(&kernel_attributes);
 
         Ok(result)
     }
@@ -172,133 +154,65 @@ impl SystemAttributes {
     ///
     /// # Returns
     /// - Ok(result): if attributes were read successfully
-    /// - Err(TockloaderError::MisconfiguredBoard): if no start address is found or valid
-    /// - Err(TockloaderError::MisconfiguredBoard): if attributes don't follow the UTF-8 format
+    /// - Err(TockError::MissingAttribute): if no start address is found
+    /// - Err(TockError::AttributeParsing): if attributes are not valid
     /// - Err(TockloaderError::SerialReadError): if reading fails
     pub(crate) async fn read_system_attributes_serial(
-        port: &mut SerialStream,
+        port: &mut BootloaderPort,
     ) -> Result<Self, TockloaderError> {
-        let mut result = SystemAttributes::new();
-
-        // System attributes start at 0x600 and up to 0x9FF. See:
-        // https://book.tockos.org/doc/memory_layout#flash-1
-        let mut pkt = (0x600_u32).to_le_bytes().to_vec();
-        // Each attribute is 64 bytes exactly, and there are 16 slots
-        let length = (1024_u16).to_le_bytes().to_vec();
-        for i in length {
-            pkt.push(i);
-        }
+        let attributes = port
+            .read_range(ATTRIBUTES_ADDRESS, ATTRIBUTE_SIZE * ATTRIBUTE_SLOTS)
+            .await?;
+        let bootloader_version = port
+            .read_range(BOOTLOADER_VERSION_ADDRESS, BOOTLOADER_VERSION_SIZE)
+            .await?;
 
-        // Read the kernel attributes
-        let (_, buf) = issue_command(
-            port,
-            Command::ReadRange,
-            pkt,
-            true,
-            64 * 16,
-            Response::ReadRange,
-        )
-        .await?;
-
-        let mut data = buf.chunks(64);
-
-        for current_slot in 0..data.len() {
-            let slot_data = match data.next() {
-                Some(data) => data,
-                None => break,
-            };
-
-            if let Some(decoded_attributes) = decode_attribute(slot_data) {
-                match current_slot {
-                    0 => {
-                        result.board = Some(decoded_attributes.value.to_string());
-                    }
-                    1 => {
-                        result.arch = Some(decoded_attributes.value.to_string());
-                    }
-                    2 => {
-                        // Parse hex string like "0x40000" into actual u64 value
-                        result.appaddr = Some(
-                            u64::from_str_radix(
-                                decoded_attributes
-                                    .value
-                                    .to_string()
-                                    .trim_start_matches("0x"),
-                                16,
-                            )
-                            .map_err(|e| {
-                                TockError::AttributeParsing(AttributeParseError::InvalidNumber(e))
-                            })?,
-                        );
-                    }
-                    3 => {
-                        result.boothash = Some(decoded_attributes.value.to_string());
-                    }
-                    _ => {}
-                }
-            } else {
-                continue;
-            }
-        }
-
-        // TODO(george-cosma): separate kernel attributes from kernel flags.
-
-        let mut pkt = (0x40E_u32).to_le_bytes().to_vec();
-        let length = (8_u16).to_le_bytes().to_vec();
-        for i in length {
-            pkt.push(i);
-        }
+        let mut result = SystemAttributes::from_attributes(&attributes, &bootloader_version)?;
 
-        // Read bootloader version
-        let (_, buf) =
-            issue_command(port, Command::ReadRange, pkt, true, 8, Response::ReadRange).await?;
+        let kernel_attributes = port
+            .read_range(result.kernel_attributes_address()?, KERNEL_ATTRIBUTES_SIZE)
+            .await?;
+        result.set_kernel_attributes(&kernel_attributes);
 
-        let string = String::from_utf8(buf)
-            .map_err(|e| TockError::AttributeParsing(AttributeParseError::InvalidString(e)))?;
-
-        // Strip null bytes
-        let string = string.trim_matches(char::from(0));
-        result.bootloader_version = Some(string.to_owned());
-
-        // The 100 bytes prior to the application start address are reserved
-        // for the kernel attributes and flags
-        let kernel_attr_addr = (result
-            .appaddr
-            .ok_or(TockError::MissingAttribute("appaddr".to_owned()))?
-            - 100) as u32;
-        let mut pkt = kernel_attr_addr.to_le_bytes().to_vec();
-        let length = (100_u16).to_le_bytes().to_vec();
-        for i in length {
-            pkt.push(i);
-        }
-
-        // Read kernel flags
-        let (_, kernel_attr_binary) = issue_command(
-            port,
-            Command::ReadRange,
-            pkt,
-            true,
-            100,
-            Response::ReadRange,
-        )
-        .await?;
-
-        let sentinel = bytes_to_string(&kernel_attr_binary[96..100]);
-        let kernel_version = LittleEndian::read_uint(&kernel_attr_binary[95..96], 1);
-
-        let app_memory_len = LittleEndian::read_u32(&kernel_attr_binary[84..92]);
-        let app_memory_start = LittleEndian::read_u32(&kernel_attr_binary[80..84]);
-
-        let kernel_binary_start = LittleEndian::read_u32(&kernel_attr_binary[68..72]);
-        let kernel_binary_len = LittleEndian::read_u32(&kernel_attr_binary[72..76]);
+        Ok(result)
+    }
+}
 
-        result.sentinel = Some(sentinel);
-        result.kernel_version = Some(kernel_version);
-        result.app_mem_start = Some(app_memory_start);
-        result.app_mem_len = Some(app_memory_len);
-        result.kernel_bin_start = Some(kernel_binary_start);
-        result.kernel_bin_len = Some(kernel_binary_len);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::decode::tests::attributes_fixture;
+
+    #[test]
+    fn from_attributes() {
+        let mut attributes =
+            SystemAttributes::from_attributes(&attributes_fixture(), b"1.1.3\0\0\0").unwrap();
+        assert_eq!(attributes.board.as_deref(), Some("nrf52dk"));
+        assert_eq!(attributes.arch.as_deref(), Some("cortex-m4"));
+        assert_eq!(attributes.appaddr, Some(0x40000));
+        assert_eq!(attributes.boothash, None);
+        assert_eq!(attributes.bootloader_version.as_deref(), Some("1.1.3"));
+        assert_eq!(
+            attributes.kernel_attributes_address().unwrap(),
+            0x40000 - 100
+        );
+
+        // Erased flash holds no kernel attributes.
+        attributes.set_kernel_attributes(&[0xFF; KERNEL_ATTRIBUTES_SIZE]);
+        assert_eq!(attributes.sentinel, None);
+        assert_eq!(attributes.kernel_version, None);
+
+        // Neither does it hold a bootloader version.
+        let attributes =
+            SystemAttributes::from_attributes(&attributes_fixture(), &[0xFF; 8]).unwrap();
+        assert_eq!(attributes.bootloader_version, None);
+    }
 
-        Ok(result)
+    #[test]
+    fn missing_appaddr() {
+        let attributes =
+            SystemAttributes::from_attributes(&[0xFF; 1024], &[0; BOOTLOADER_VERSION_SIZE])
+                .unwrap();
+        assert!(attributes.kernel_attributes_address().is_err());
     }
 }
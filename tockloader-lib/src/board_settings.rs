@@ -1,6 +1,15 @@
+use crate::flash_geometry::FlashGeometry;
+
 pub struct BoardSettings {
     pub arch: Option<String>,
     pub start_address: u64,
+    /// Address of the kernel binary, used when the board does not report it
+    /// in its kernel attributes.
+    pub kernel_address: Option<u64>,
+    /// Erase geometry of the flash. When `None`, probe connections use the
+    /// flash algorithm of the target and serial connections use
+    /// [DEFAULT_PAGE_SIZE](crate::flash_geometry::DEFAULT_PAGE_SIZE) pages.
+    pub flash_geometry: Option<FlashGeometry>,
 }
 
 // TODO(george-cosma): Does a default implementation make sense for this? Is a
@@ -10,6 +19,8 @@ impl Default for BoardSettings {
         Self {
             arch: None,
             start_address: 0x30000,
+            kernel_address: None,
+            flash_geometry: None,
         }
     }
 }
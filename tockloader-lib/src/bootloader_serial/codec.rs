@@ -0,0 +1,250 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+//! Framing of the serial bootloader protocol.
+//!
+//! A request is the escaped payload followed by [ESCAPE_CHAR] and the command
+//! byte, optionally preceded by [SYNC_MESSAGE]. A response is [ESCAPE_CHAR],
+//! the response byte and the escaped payload. Escaping doubles every
+//! [ESCAPE_CHAR] of a payload, so the number of bytes on the wire depends on
+//! the data: the decoder counts decoded bytes instead.
+
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{Command, Response, ESCAPE_CHAR, SYNC_MESSAGE};
+use crate::errors::{TockError, TockloaderError};
+
+/// Error of a [BootloaderCodec]. The codec traits need errors that can be
+/// built from an [io::Error], which here can only come from the serial port.
+#[derive(Debug)]
+pub struct CodecError(pub TockloaderError);
+
+impl From<io::Error> for CodecError {
+    fn from(value: io::Error) -> Self {
+        CodecError(TockloaderError::Serial(value.into()))
+    }
+}
+
+impl From<TockError> for CodecError {
+    fn from(value: TockError) -> Self {
+        CodecError(value.into())
+    }
+}
+
+impl From<CodecError> for TockloaderError {
+    fn from(value: CodecError) -> Self {
+        value.0
+    }
+}
+
+/// A command sent to the bootloader.
+pub struct Request {
+    pub command: Command,
+    pub payload: Vec<u8>,
+    /// Prepend [SYNC_MESSAGE], which resets the bootloader command buffer.
+    pub sync: bool,
+}
+
+/// Codec for a single request and its response. The response is expected to
+/// carry a payload of a known length once de-escaped.
+pub struct BootloaderCodec {
+    response: Response,
+    response_len: usize,
+    state: DecodeState,
+}
+
+enum DecodeState {
+    Header,
+    Payload { payload: Vec<u8>, escaped: bool },
+}
+
+impl BootloaderCodec {
+    /// Expect a `response` whose payload is `response_len` bytes long.
+    pub fn new(response: Response, response_len: usize) -> Self {
+        BootloaderCodec {
+            response,
+            response_len,
+            state: DecodeState::Header,
+        }
+    }
+}
+
+impl Encoder<Request> for BootloaderCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, request: Request, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(SYNC_MESSAGE.len() + request.payload.len() + 2);
+        if request.sync {
+            dst.put_slice(&SYNC_MESSAGE);
+        }
+        for byte in request.payload {
+            dst.put_u8(byte);
+            if byte == ESCAPE_CHAR {
+                dst.put_u8(ESCAPE_CHAR);
+            }
+        }
+        dst.put_u8(ESCAPE_CHAR);
+        dst.put_u8(request.command as u8);
+        Ok(())
+    }
+}
+
+impl Decoder for BootloaderCodec {
+    type Item = (Response, Vec<u8>);
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let DecodeState::Header = self.state {
+            if src.len() < 2 {
+                return Ok(None);
+            }
+            if src[0..2] != [ESCAPE_CHAR, self.response as u8] {
+                // The bootloader answers with an error response instead of
+                // the expected one when it cannot run the command.
+                let error = match src[0] {
+                    ESCAPE_CHAR => Response::try_from(src[1]).ok().and_then(Response::error),
+                    _ => None,
+                };
+                return Err(error
+                    .unwrap_or(TockError::BootloaderBadHeader(src[0], src[1]))
+                    .into());
+            }
+            src.advance(2);
+            self.state = DecodeState::Payload {
+                payload: Vec::with_capacity(self.response_len),
+                escaped: false,
+            };
+        }
+
+        let DecodeState::Payload { payload, escaped } = &mut self.state else {
+            unreachable!("the header was decoded above");
+        };
+        while payload.len() < self.response_len {
+            if !src.has_remaining() {
+                return Ok(None);
+            }
+            let byte = src.get_u8();
+            if *escaped {
+                if byte != ESCAPE_CHAR {
+                    return Err(TockError::BootloaderBadEscape(byte).into());
+                }
+                payload.push(ESCAPE_CHAR);
+                *escaped = false;
+            } else if byte == ESCAPE_CHAR {
+                *escaped = true;
+            } else {
+                payload.push(byte);
+            }
+        }
+
+        let payload = std::mem::take(payload);
+        self.state = DecodeState::Header;
+        Ok(Some((self.response, payload)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Frame = <BootloaderCodec as Decoder>::Item;
+
+    fn encode(payload: &[u8], sync: bool) -> Vec<u8> {
+        let mut dst = BytesMut::new();
+        BootloaderCodec::new(Response::OK, 0)
+            .encode(
+                Request {
+                    command: Command::WritePage,
+                    payload: payload.to_vec(),
+                    sync,
+                },
+                &mut dst,
+            )
+            .unwrap();
+        dst.to_vec()
+    }
+
+    /// Feed `wire` to a decoder `step` bytes at a time.
+    fn decode_in_steps(
+        wire: &[u8],
+        response_len: usize,
+        step: usize,
+    ) -> Result<(Option<Frame>, BytesMut), TockloaderError> {
+        let mut codec = BootloaderCodec::new(Response::ReadRange, response_len);
+        let mut src = BytesMut::new();
+        for chunk in wire.chunks(step) {
+            src.extend_from_slice(chunk);
+            if let Some(frame) = codec.decode(&mut src)? {
+                return Ok((Some(frame), src));
+            }
+        }
+        Ok((None, src))
+    }
+
+    #[test]
+    fn encode_request() {
+        assert_eq!(encode(&[1, 2], false), vec![1, 2, 0xFC, 0x07]);
+        assert_eq!(
+            encode(&[0xFC, 0x00, 0xFC, 0xFC], true),
+            vec![0x00, 0xFC, 0x05, 0xFC, 0xFC, 0x00, 0xFC, 0xFC, 0xFC, 0xFC, 0xFC, 0x07]
+        );
+    }
+
+    #[test]
+    fn escaped_payload() {
+        // Decoded payload: FC 01 FC FC, which takes 7 bytes on the wire.
+        let wire = [0xFC, 0x20, 0xFC, 0xFC, 0x01, 0xFC, 0xFC, 0xFC, 0xFC, 0xAA];
+
+        for step in 1..=wire.len() {
+            let (frame, rest) = decode_in_steps(&wire, 4, step).unwrap();
+            let (response, payload) = frame.unwrap();
+            assert_eq!(response, Response::ReadRange);
+            assert_eq!(payload, vec![0xFC, 0x01, 0xFC, 0xFC]);
+            // The byte after the frame is left for the next one.
+            assert_eq!(&rest[..], &wire[9..9 + rest.len()]);
+        }
+    }
+
+    #[test]
+    fn partial_frames() {
+        // An escape pair split between reads.
+        let wire = [0xFC, 0x20, 0x01, 0xFC];
+        assert!(decode_in_steps(&wire, 2, 1).unwrap().0.is_none());
+        assert!(decode_in_steps(&wire[..1], 0, 1).unwrap().0.is_none());
+
+        let (frame, _) = decode_in_steps(&[0xFC, 0x20], 0, 1).unwrap();
+        assert_eq!(frame.unwrap().1, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn invalid_frames() {
+        assert!(matches!(
+            decode_in_steps(&[0xFC, 0x12], 4, 2),
+            Err(TockloaderError::Tock(TockError::BootloaderBadAddress))
+        ));
+        assert!(matches!(
+            decode_in_steps(&[0xFC, 0x16], 4, 2),
+            Err(TockloaderError::Tock(TockError::BootloaderUnknownCommand))
+        ));
+        assert!(matches!(
+            decode_in_steps(&[0xFC, 0x15], 4, 2),
+            Err(TockloaderError::Tock(TockError::BootloaderBadHeader(
+                0xFC, 0x15
+            )))
+        ));
+        assert!(matches!(
+            decode_in_steps(&[0x00, 0x20], 4, 2),
+            Err(TockloaderError::Tock(TockError::BootloaderBadHeader(
+                0x00, 0x20
+            )))
+        ));
+        assert!(matches!(
+            decode_in_steps(&[0xFC, 0x20, 0x01, 0xFC, 0x02], 4, 5),
+            Err(TockloaderError::Tock(TockError::BootloaderBadEscape(0x02)))
+        ));
+    }
+}
@@ -0,0 +1,528 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+// The "X" commands are for external flash
+
+pub mod codec;
+
+use crate::connection::RetryPolicy;
+use crate::errors::{self, InternalError, TockError};
+use crate::flash_geometry::DEFAULT_PAGE_SIZE;
+use bytes::BytesMut;
+use codec::{BootloaderCodec, Request};
+use errors::TockloaderError;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_serial::{ClearBuffer, SerialPort, SerialStream};
+use tokio_util::codec::{Decoder, Encoder};
+
+// Tell the bootloader to reset its buffer to handle a new command
+pub const SYNC_MESSAGE: [u8; 3] = [0x00, 0xFC, 0x05];
+
+// "This was chosen as it is infrequent in .bin files" - immesys
+pub const ESCAPE_CHAR: u8 = 0xFC;
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+pub enum Command {
+    // Commands from this tool to the bootloader
+    Ping = 0x01,
+    Info = 0x03,
+    ID = 0x04,
+    Reset = 0x05,
+    ErasePage = 0x06,
+    WritePage = 0x07,
+    XEBlock = 0x08,
+    XWPage = 0x09,
+    Crcx = 0x10,
+    ReadRange = 0x11,
+    XRRange = 0x12,
+    SetAttribute = 0x13,
+    GetAttribute = 0x14,
+    CRCInternalFlash = 0x15,
+    Crcef = 0x16,
+    XEPage = 0x17,
+    XFinit = 0x18,
+    ClkOut = 0x19,
+    WUser = 0x20,
+    ChangeBaudRate = 0x21,
+    Exit = 0x22,
+    SetStartAddress = 0x23,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Response {
+    // Responses from the bootloader
+    Overflow = 0x10,
+    Pong = 0x11,
+    BadAddr = 0x12,
+    IntError = 0x13,
+    BadArgs = 0x14,
+    OK = 0x15,
+    Unknown = 0x16,
+    XFTimeout = 0x17,
+    Xfepe = 0x18,
+    Crcrx = 0x19,
+    ReadRange = 0x20,
+    XRRange = 0x21,
+    GetAttribute = 0x22,
+    CRCInternalFlash = 0x23,
+    Crcxf = 0x24,
+    Info = 0x25,
+    ChangeBaudFail = 0x26,
+}
+
+impl TryFrom<u8> for Response {
+    /// The unknown response code.
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0x10 => Response::Overflow,
+            0x11 => Response::Pong,
+            0x12 => Response::BadAddr,
+            0x13 => Response::IntError,
+            0x14 => Response::BadArgs,
+            0x15 => Response::OK,
+            0x16 => Response::Unknown,
+            0x17 => Response::XFTimeout,
+            0x18 => Response::Xfepe,
+            0x19 => Response::Crcrx,
+            0x20 => Response::ReadRange,
+            0x21 => Response::XRRange,
+            0x22 => Response::GetAttribute,
+            0x23 => Response::CRCInternalFlash,
+            0x24 => Response::Crcxf,
+            0x25 => Response::Info,
+            0x26 => Response::ChangeBaudFail,
+            _ => return Err(value),
+        })
+    }
+}
+
+impl Response {
+    /// The error reported by an error response, or `None` for the other
+    /// responses.
+    pub fn error(self) -> Option<TockError> {
+        match self {
+            Response::Overflow => Some(TockError::BootloaderOverflow),
+            Response::BadAddr => Some(TockError::BootloaderBadAddress),
+            Response::IntError => Some(TockError::BootloaderInternalError),
+            Response::BadArgs => Some(TockError::BootloaderBadArguments),
+            Response::Unknown => Some(TockError::BootloaderUnknownCommand),
+            Response::Crcrx => Some(TockError::BootloaderCrcMismatch),
+            Response::XFTimeout => Some(TockError::ExternalFlashTimeout),
+            Response::Xfepe => Some(TockError::ExternalFlashEraseFailed),
+            _ => None,
+        }
+    }
+}
+
+/// How long to wait for the answer to a single ping. The bootloader answers
+/// pings right away, so there is no need to wait for as long as for other
+/// commands.
+const PING_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Byte stream connected to the bootloader.
+pub trait BootloaderStream: AsyncRead + AsyncWrite + Unpin + Send {
+    /// Drop the bytes that were received but not read yet.
+    fn clear_input(&mut self) -> Result<(), TockloaderError>;
+}
+
+impl BootloaderStream for SerialStream {
+    fn clear_input(&mut self) -> Result<(), TockloaderError> {
+        Ok(self.clear(ClearBuffer::Input)?)
+    }
+}
+
+/// Serial port connected to the bootloader, along with the policy used to
+/// recover from transient errors.
+pub struct BootloaderPort<S = SerialStream> {
+    pub stream: S,
+    pub retry: RetryPolicy,
+}
+
+/// Whether a command that failed with `error` may succeed if sent again.
+/// Errors that depend on the command itself, such as a bad address, are not
+/// transient.
+fn is_transient(error: &TockloaderError) -> bool {
+    matches!(
+        error,
+        TockloaderError::Tock(
+            TockError::BootloaderTimeout
+                | TockError::BootloaderBadHeader(_, _)
+                | TockError::BootloaderBadEscape(_)
+                | TockError::BootloaderOverflow
+                | TockError::BootloaderCrcMismatch
+        )
+    )
+}
+
+/// Reset the board into the bootloader, for boards that wire DTR to the reset
+/// line and RTS to the bootloader select pin.
+pub async fn toggle_bootloader_entry_dtr_rts(
+    port: &mut SerialStream,
+) -> Result<(), TockloaderError> {
+    port.write_data_terminal_ready(true)?;
+    port.write_request_to_send(true)?;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    port.write_data_terminal_ready(false)?;
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    port.write_request_to_send(false)?;
+
+    Ok(())
+}
+
+async fn read_bytes(
+    port: &mut impl BootloaderStream,
+    bytes_to_read: usize,
+    timeout: Duration,
+) -> Result<BytesMut, TockloaderError> {
+    let mut ret = BytesMut::with_capacity(bytes_to_read);
+    let mut read_bytes = 0;
+
+    tokio::time::timeout(timeout, async {
+        while read_bytes < bytes_to_read {
+            read_bytes += port
+                .read_buf(&mut ret)
+                .await
+                .map_err(|e| TockloaderError::Serial(e.into()))?;
+        }
+        Ok(ret)
+    })
+    .await
+    .map_err(|_| TockError::BootloaderTimeout)?
+}
+
+async fn write_bytes(
+    port: &mut impl BootloaderStream,
+    bytes: &[u8],
+    timeout: Duration,
+) -> Result<(), TockloaderError> {
+    let mut bytes_written = 0;
+
+    tokio::time::timeout(timeout, async {
+        while bytes_written != bytes.len() {
+            bytes_written += port
+                .write_buf(&mut &bytes[bytes_written..])
+                .await
+                .map_err(|e| TockloaderError::Serial(e.into()))?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|_| TockError::BootloaderTimeout)?
+}
+
+/// Ping the bootloader until it answers. A ping that is not answered in time
+/// is sent again, as the bootloader may still be busy with a previous
+/// command.
+pub async fn ping_bootloader_and_wait_for_response<S: BootloaderStream>(
+    port: &mut BootloaderPort<S>,
+) -> Result<(), TockloaderError> {
+    let ping_pkt = [ESCAPE_CHAR, Command::Ping as u8];
+    let timeout = port.retry.timeout.min(PING_TIMEOUT);
+
+    for _ in 0..30 {
+        write_bytes(&mut port.stream, &ping_pkt, timeout).await?;
+        let ret = match read_bytes(&mut port.stream, 2, timeout).await {
+            Ok(ret) => ret,
+            Err(TockloaderError::Tock(TockError::BootloaderTimeout)) => continue,
+            Err(e) => return Err(e),
+        };
+
+        if ret[1] == Response::Pong as u8 {
+            return Ok(());
+        }
+    }
+
+    Err(InternalError::BootloaderNotPresent.into())
+}
+
+/// Drop whatever is left of a failed exchange, reset the command buffer of
+/// the bootloader and check that it still answers.
+async fn resync<S: BootloaderStream>(port: &mut BootloaderPort<S>) -> Result<(), TockloaderError> {
+    port.stream.clear_input()?;
+    write_bytes(&mut port.stream, &SYNC_MESSAGE, port.retry.timeout).await?;
+    ping_bootloader_and_wait_for_response(port).await
+}
+
+/// Send a command and wait for its response. Commands that fail with a
+/// transient error are retried according to the [RetryPolicy] of `port`.
+pub async fn issue_command<S: BootloaderStream>(
+    port: &mut BootloaderPort<S>,
+    command: Command,
+    message: Vec<u8>,
+    sync: bool,
+    response_len: usize,
+    response_code: Response,
+) -> Result<(Response, Vec<u8>), TockloaderError> {
+    let retries = port.retry.retries;
+    let mut attempt = 0;
+    let mut needs_resync = false;
+    loop {
+        if needs_resync {
+            // A bootloader that does not answer the resync may still recover,
+            // so this counts as one more failed attempt.
+            if let Err(e) = resync(port).await {
+                if attempt >= retries {
+                    return Err(e);
+                }
+                attempt += 1;
+                log::warn!(
+                    "Resync with the bootloader failed ({e}), retrying ({attempt}/{retries})"
+                );
+                continue;
+            }
+        }
+
+        let result = exchange(
+            &mut port.stream,
+            command,
+            message.clone(),
+            sync,
+            response_len,
+            response_code,
+            port.retry.timeout,
+        )
+        .await;
+
+        match result {
+            Err(e) if is_transient(&e) && attempt < retries => {
+                attempt += 1;
+                log::warn!(
+                    "Bootloader command {command:?} failed ({e}), retrying ({attempt}/{retries})"
+                );
+                needs_resync = port.retry.resync;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Ask the bootloader to exit and start the kernel. The bootloader jumps to
+/// the kernel without answering, so no response is read.
+pub async fn exit_bootloader(port: &mut BootloaderPort) -> Result<(), TockloaderError> {
+    let mut request = BytesMut::new();
+    BootloaderCodec::new(Response::OK, 0).encode(
+        Request {
+            command: Command::Exit,
+            payload: vec![],
+            sync: true,
+        },
+        &mut request,
+    )?;
+    write_bytes(&mut port.stream, &request, port.retry.timeout).await
+}
+
+/// Send a command once and wait for its response.
+async fn exchange(
+    port: &mut impl BootloaderStream,
+    command: Command,
+    message: Vec<u8>,
+    sync: bool,
+    response_len: usize,
+    response_code: Response,
+    timeout: Duration,
+) -> Result<(Response, Vec<u8>), TockloaderError> {
+    let mut codec = BootloaderCodec::new(response_code, response_len);
+
+    // Write the command message
+    let mut request = BytesMut::new();
+    codec.encode(
+        Request {
+            command,
+            payload: message,
+            sync,
+        },
+        &mut request,
+    )?;
+    write_bytes(port, &request, timeout).await?;
+
+    // Response has a two byte header, then response_len bytes once de-escaped
+    read_frame(port, &mut codec, timeout).await
+}
+
+/// Read from `port` until `codec` decodes a full response.
+async fn read_frame(
+    port: &mut impl BootloaderStream,
+    codec: &mut BootloaderCodec,
+    timeout: Duration,
+) -> Result<(Response, Vec<u8>), TockloaderError> {
+    let mut buf = BytesMut::new();
+
+    tokio::time::timeout(timeout, async {
+        loop {
+            if let Some(frame) = codec.decode(&mut buf)? {
+                if !buf.is_empty() {
+                    log::debug!("Dropping {} bytes after the response", buf.len());
+                }
+                return Ok(frame);
+            }
+            port.read_buf(&mut buf)
+                .await
+                .map_err(|e| TockloaderError::Serial(e.into()))?;
+        }
+    })
+    .await
+    .map_err(|_| TockError::BootloaderTimeout)?
+}
+
+/// Convert `address` for a command packet: the tockloader protocol only
+/// supports 32-bit addresses.
+pub(crate) fn bootloader_address(address: u64) -> Result<u32, TockError> {
+    u32::try_from(address).map_err(|_| TockError::BootloaderAddressOutOfRange(address))
+}
+
+/// Write `data` at `address`, which must be aligned to a bootloader page, with
+/// one `WritePage` command per [DEFAULT_PAGE_SIZE] bytes. The last page is
+/// padded with 0xFF.
+pub async fn write_pages(
+    port: &mut BootloaderPort,
+    address: u64,
+    data: &[u8],
+) -> Result<(), TockloaderError> {
+    let page_size = DEFAULT_PAGE_SIZE as usize;
+    for (i, page) in data.chunks(page_size).enumerate() {
+        // First four bytes are the address of the page
+        let mut pkt = (address as u32 + (i * page_size) as u32)
+            .to_le_bytes()
+            .to_vec();
+        // Then the bytes that go into the page
+        pkt.extend(page);
+        pkt.resize(4 + page_size, 0xFF);
+
+        let (_, _) = issue_command(port, Command::WritePage, pkt, true, 0, Response::OK).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::DuplexStream;
+
+    use super::*;
+
+    impl BootloaderStream for DuplexStream {
+        fn clear_input(&mut self) -> Result<(), TockloaderError> {
+            Ok(())
+        }
+    }
+
+    /// Bootloader that ignores the first `ErasePage` and the first ping, then
+    /// answers everything.
+    async fn flaky_bootloader(mut stream: DuplexStream) -> Vec<Command> {
+        let mut commands = vec![];
+        let mut received = vec![];
+        let mut buf = [0; 64];
+        while let Ok(n @ 1..) = stream.read(&mut buf).await {
+            received.extend_from_slice(&buf[..n]);
+            // Every frame this test sends ends with an escape and a command.
+            while let Some(i) = received
+                .windows(2)
+                .position(|pair| pair[0] == ESCAPE_CHAR && pair[1] != ESCAPE_CHAR)
+            {
+                let command = received[i + 1];
+                received.drain(..i + 2);
+                let response = match command {
+                    0x01 => {
+                        commands.push(Command::Ping);
+                        Response::Pong
+                    }
+                    0x06 => {
+                        commands.push(Command::ErasePage);
+                        Response::OK
+                    }
+                    _ => continue,
+                };
+                let first = commands.iter().filter(|&&c| c as u8 == command).count() == 1;
+                if !first {
+                    let _ = stream.write_all(&[ESCAPE_CHAR, response as u8]).await;
+                }
+            }
+        }
+        commands
+    }
+
+    #[tokio::test]
+    async fn resync_survives_ping_timeout() {
+        let (client, server) = tokio::io::duplex(1024);
+        let bootloader = tokio::spawn(flaky_bootloader(server));
+        let mut port = BootloaderPort {
+            stream: client,
+            retry: RetryPolicy {
+                retries: 1,
+                timeout: Duration::from_millis(100),
+                resync: true,
+            },
+        };
+
+        let pkt = 0x40000u32.to_le_bytes().to_vec();
+        let (response, _) =
+            issue_command(&mut port, Command::ErasePage, pkt, true, 0, Response::OK)
+                .await
+                .unwrap();
+        assert_eq!(response, Response::OK);
+
+        drop(port);
+        let commands = bootloader.await.unwrap();
+        // The command times out, the resync needs two pings, then the command
+        // is sent again.
+        assert!(matches!(
+            commands[..],
+            [
+                Command::ErasePage,
+                Command::Ping,
+                Command::Ping,
+                Command::ErasePage
+            ]
+        ));
+    }
+
+    #[test]
+    fn transient_errors() {
+        assert!(is_transient(&TockError::BootloaderTimeout.into()));
+        assert!(is_transient(
+            &TockError::BootloaderBadHeader(0x00, 0x11).into()
+        ));
+        assert!(is_transient(&Response::Crcrx.error().unwrap().into()));
+        assert!(!is_transient(&Response::BadAddr.error().unwrap().into()));
+        assert!(!is_transient(&Response::BadArgs.error().unwrap().into()));
+        assert!(!is_transient(&InternalError::BootloaderNotPresent.into()));
+    }
+
+    #[test]
+    fn error_responses() {
+        for response in [
+            Response::Overflow,
+            Response::BadAddr,
+            Response::IntError,
+            Response::BadArgs,
+            Response::Unknown,
+            Response::Crcrx,
+            Response::XFTimeout,
+            Response::Xfepe,
+        ] {
+            assert!(response.error().is_some(), "{response:?}");
+        }
+        assert!(Response::OK.error().is_none());
+        assert!(Response::ReadRange.error().is_none());
+
+        assert_eq!(Response::try_from(0x12), Ok(Response::BadAddr));
+        assert_eq!(Response::try_from(0x30), Err(0x30));
+    }
+
+    #[test]
+    fn address_range() {
+        assert_eq!(bootloader_address(0xFFFF_FFFF).unwrap(), 0xFFFF_FFFF);
+        assert!(matches!(
+            bootloader_address(0x1_0000_0000),
+            Err(TockError::BootloaderAddressOutOfRange(0x1_0000_0000))
+        ));
+    }
+}
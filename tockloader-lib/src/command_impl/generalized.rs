@@ -1,12 +1,20 @@
 use async_trait::async_trait;
 
 use crate::attributes::app_attributes::AppAttributes;
+use crate::attributes::bootloader_info::BootloaderInfo;
 use crate::attributes::general_attributes::GeneralAttributes;
 use crate::board_settings::BoardSettings;
 use crate::connection::TockloaderConnection;
+use crate::credentials::keys::PublicKey;
+use crate::credentials::verify::AppVerification;
 use crate::errors::TockloaderError;
+use crate::kernel::KernelImage;
 use crate::tabs::tab::Tab;
-use crate::{CommandEraseApps, CommandInfo, CommandInstall, CommandList};
+use crate::{
+    CommandAttributes, CommandBootloaderInfo, CommandEraseApps, CommandExternalFlash,
+    CommandFlashBootloader, CommandFlashKernel, CommandInfo, CommandInstall, CommandList,
+    CommandReset, CommandVerify, ResetMode,
+};
 
 #[async_trait]
 impl CommandList for TockloaderConnection {
@@ -57,3 +65,130 @@ impl CommandEraseApps for TockloaderConnection {
         }
     }
 }
+
+#[async_trait]
+impl CommandReset for TockloaderConnection {
+    async fn reset(&mut self, mode: ResetMode) -> Result<(), TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.reset(mode).await,
+            TockloaderConnection::Serial(conn) => conn.reset(mode).await,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandVerify for TockloaderConnection {
+    async fn verify_apps(
+        &mut self,
+        apps: &[AppAttributes],
+        keys: &[PublicKey],
+    ) -> Result<Vec<AppVerification>, TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.verify_apps(apps, keys).await,
+            TockloaderConnection::Serial(conn) => conn.verify_apps(apps, keys).await,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandAttributes for TockloaderConnection {
+    async fn get_attribute(&mut self, key: &str) -> Result<String, TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.get_attribute(key).await,
+            TockloaderConnection::Serial(conn) => conn.get_attribute(key).await,
+        }
+    }
+
+    async fn set_attribute(&mut self, key: &str, value: &str) -> Result<(), TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.set_attribute(key, value).await,
+            TockloaderConnection::Serial(conn) => conn.set_attribute(key, value).await,
+        }
+    }
+
+    async fn remove_attribute(&mut self, key: &str) -> Result<(), TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.remove_attribute(key).await,
+            TockloaderConnection::Serial(conn) => conn.remove_attribute(key).await,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandBootloaderInfo for TockloaderConnection {
+    async fn bootloader_info(&mut self) -> Result<BootloaderInfo, TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.bootloader_info().await,
+            TockloaderConnection::Serial(conn) => conn.bootloader_info().await,
+        }
+    }
+
+    async fn board_id(&mut self) -> Result<Vec<u8>, TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.board_id().await,
+            TockloaderConnection::Serial(conn) => conn.board_id().await,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandExternalFlash for TockloaderConnection {
+    async fn read_external(
+        &mut self,
+        address: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.read_external(address, length).await,
+            TockloaderConnection::Serial(conn) => conn.read_external(address, length).await,
+        }
+    }
+
+    async fn write_external(&mut self, address: u64, data: &[u8]) -> Result<(), TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.write_external(address, data).await,
+            TockloaderConnection::Serial(conn) => conn.write_external(address, data).await,
+        }
+    }
+
+    async fn erase_external(&mut self, address: u64, length: u64) -> Result<(), TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.erase_external(address, length).await,
+            TockloaderConnection::Serial(conn) => conn.erase_external(address, length).await,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandFlashBootloader for TockloaderConnection {
+    async fn flash_bootloader(
+        &mut self,
+        binary: &[u8],
+        address: u64,
+        force: bool,
+    ) -> Result<(), TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => {
+                conn.flash_bootloader(binary, address, force).await
+            }
+            TockloaderConnection::Serial(conn) => {
+                conn.flash_bootloader(binary, address, force).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CommandFlashKernel for TockloaderConnection {
+    async fn flash_kernel(
+        &mut self,
+        settings: &BoardSettings,
+        kernel: &KernelImage,
+        force: bool,
+    ) -> Result<(), TockloaderError> {
+        match self {
+            TockloaderConnection::ProbeRS(conn) => conn.flash_kernel(settings, kernel, force).await,
+            TockloaderConnection::Serial(conn) => conn.flash_kernel(settings, kernel, force).await,
+        }
+    }
+}
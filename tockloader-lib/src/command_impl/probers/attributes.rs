@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use probe_rs::flashing::DownloadOptions;
+use probe_rs::MemoryInterface;
+
+use crate::attributes::decode::{
+    decode_attributes, encode_attribute, find_attribute, find_attribute_slot, slot_for_attribute,
+    DecodedAttribute, ATTRIBUTES_ADDRESS, ATTRIBUTE_SIZE, ATTRIBUTE_SLOTS, EMPTY_ATTRIBUTE,
+};
+use crate::connection::{Connection, ProbeRSConnection};
+use crate::errors::{InternalError, TockError, TockloaderError};
+use crate::CommandAttributes;
+
+impl ProbeRSConnection {
+    /// Read and decode every attribute slot.
+    fn read_attributes(&mut self) -> Result<Vec<(usize, DecodedAttribute)>, TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let session = self.session.as_mut().expect("Board must be open");
+        let mut core = session.core(self.target_info.core)?;
+
+        let mut data = vec![0u8; ATTRIBUTE_SIZE * ATTRIBUTE_SLOTS];
+        core.read(ATTRIBUTES_ADDRESS, &mut data)?;
+        Ok(decode_attributes(&data))
+    }
+
+    /// Overwrite a single attribute slot, keeping the rest of the flash page.
+    fn write_attribute_slot(
+        &mut self,
+        slot: usize,
+        data: &[u8; ATTRIBUTE_SIZE],
+    ) -> Result<(), TockloaderError> {
+        let session = self.session.as_mut().expect("Board must be open");
+
+        let mut loader = session.target().flash_loader();
+        loader.add_data(ATTRIBUTES_ADDRESS + (slot * ATTRIBUTE_SIZE) as u64, data)?;
+
+        let mut options = DownloadOptions::default();
+        options.keep_unwritten_bytes = true;
+
+        loader.commit(session, options)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CommandAttributes for ProbeRSConnection {
+    async fn get_attribute(&mut self, key: &str) -> Result<String, TockloaderError> {
+        let attributes = self.read_attributes()?;
+        find_attribute(&attributes, key)
+            .map(str::to_string)
+            .ok_or_else(|| TockError::MissingAttribute(key.to_string()).into())
+    }
+
+    async fn set_attribute(&mut self, key: &str, value: &str) -> Result<(), TockloaderError> {
+        let data = encode_attribute(key, value)?;
+        let attributes = self.read_attributes()?;
+        let slot = slot_for_attribute(&attributes, key)?;
+        self.write_attribute_slot(slot, &data)
+    }
+
+    async fn remove_attribute(&mut self, key: &str) -> Result<(), TockloaderError> {
+        let attributes = self.read_attributes()?;
+        let slot = find_attribute_slot(&attributes, key)?;
+        self.write_attribute_slot(slot, &EMPTY_ATTRIBUTE)
+    }
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use crate::attributes::bootloader_info::BootloaderInfo;
+use crate::connection::ProbeRSConnection;
+use crate::errors::{InternalError, TockloaderError};
+use crate::CommandBootloaderInfo;
+
+#[async_trait]
+impl CommandBootloaderInfo for ProbeRSConnection {
+    async fn bootloader_info(&mut self) -> Result<BootloaderInfo, TockloaderError> {
+        // The bootloader only answers commands over serial, and does not run
+        // while a probe holds the core.
+        Err(InternalError::BootloaderRequired.into())
+    }
+
+    async fn board_id(&mut self) -> Result<Vec<u8>, TockloaderError> {
+        Err(InternalError::BootloaderRequired.into())
+    }
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+
+use crate::connection::ProbeRSConnection;
+use crate::errors::{InternalError, TockloaderError};
+use crate::CommandExternalFlash;
+
+#[async_trait]
+impl CommandExternalFlash for ProbeRSConnection {
+    // External flash sits behind a peripheral that only the bootloader
+    // drives, so it cannot be reached through the debug port.
+
+    async fn read_external(
+        &mut self,
+        _address: u64,
+        _length: usize,
+    ) -> Result<Vec<u8>, TockloaderError> {
+        Err(InternalError::BootloaderRequired.into())
+    }
+
+    async fn write_external(&mut self, _address: u64, _data: &[u8]) -> Result<(), TockloaderError> {
+        Err(InternalError::BootloaderRequired.into())
+    }
+
+    async fn erase_external(&mut self, _address: u64, _length: u64) -> Result<(), TockloaderError> {
+        Err(InternalError::BootloaderRequired.into())
+    }
+}
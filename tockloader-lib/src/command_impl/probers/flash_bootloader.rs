@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use probe_rs::flashing::DownloadOptions;
+use probe_rs::MemoryInterface;
+
+use crate::attributes::bootloader_version::{
+    binary_bootloader_version, check_bootloader_upgrade, decode_bootloader_version,
+    BOOTLOADER_VERSION_ADDRESS, BOOTLOADER_VERSION_SIZE,
+};
+use crate::connection::{Connection, ProbeRSConnection};
+use crate::errors::{InternalError, TockError, TockloaderError};
+use crate::CommandFlashBootloader;
+
+impl ProbeRSConnection {
+    /// Read the version of the bootloader currently on the board.
+    fn read_bootloader_version(&mut self) -> Result<Option<String>, TockloaderError> {
+        let session = self.session.as_mut().expect("Board must be open");
+        let mut core = session.core(self.target_info.core)?;
+
+        let mut version = [0u8; BOOTLOADER_VERSION_SIZE];
+        core.read_8(BOOTLOADER_VERSION_ADDRESS, &mut version)?;
+        Ok(decode_bootloader_version(&version))
+    }
+}
+
+#[async_trait]
+impl CommandFlashBootloader for ProbeRSConnection {
+    async fn flash_bootloader(
+        &mut self,
+        binary: &[u8],
+        address: u64,
+        force: bool,
+    ) -> Result<(), TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+
+        let current = self.read_bootloader_version()?;
+        let new = binary_bootloader_version(binary, address);
+        log::info!(
+            "Flashing bootloader {} over {}",
+            new.as_deref().unwrap_or("of unknown version"),
+            current.as_deref().unwrap_or("unknown bootloader")
+        );
+        check_bootloader_upgrade(current.as_deref(), new.as_deref(), force)?;
+
+        let session = self.session.as_mut().expect("Board must be open");
+        let mut loader = session.target().flash_loader();
+        loader.add_data(address, binary)?;
+
+        let mut options = DownloadOptions::default();
+        options.keep_unwritten_bytes = true;
+
+        loader.commit(session, options)?;
+
+        let found = self.read_bootloader_version()?;
+        if let Some(expected) = new {
+            if found.as_ref() != Some(&expected) {
+                return Err(TockError::BootloaderVersionMismatch { expected, found }.into());
+            }
+        }
+        log::info!(
+            "Bootloader version is now {}",
+            found.as_deref().unwrap_or("unknown")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use async_trait::async_trait;
+use probe_rs::flashing::DownloadOptions;
+
+use crate::attributes::system_attributes::SystemAttributes;
+use crate::board_settings::BoardSettings;
+use crate::connection::{Connection, ProbeRSConnection};
+use crate::errors::{InternalError, TockloaderError};
+use crate::kernel::KernelImage;
+use crate::CommandFlashKernel;
+
+#[async_trait]
+impl CommandFlashKernel for ProbeRSConnection {
+    async fn flash_kernel(
+        &mut self,
+        settings: &BoardSettings,
+        kernel: &KernelImage,
+        force: bool,
+    ) -> Result<(), TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let session = self.session.as_mut().expect("Board must be open");
+
+        // Boards flashed through a probe do not always have a bootloader, in
+        // which case the board settings are used instead.
+        let mut core = session.core(self.target_info.core)?;
+        let system = match SystemAttributes::read_system_attributes_probe(&mut core) {
+            Ok(system) => Some(system),
+            Err(e) => {
+                log::info!("Could not read system attributes ({e}), using board settings.");
+                None
+            }
+        };
+        drop(core);
+
+        let address = kernel.placement(system.as_ref(), settings, force)?;
+        log::info!("Writing {} byte kernel at {address:#x}", kernel.data.len());
+
+        let mut loader = session.target().flash_loader();
+        loader.add_data(address, &kernel.data)?;
+
+        let mut options = DownloadOptions::default();
+        options.keep_unwritten_bytes = true;
+
+        loader.commit(session, options)?;
+        Ok(())
+    }
+}
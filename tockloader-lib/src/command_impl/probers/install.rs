@@ -1,4 +1,7 @@
+use std::time::Instant;
+
 use async_trait::async_trait;
+use probe_rs::config::MemoryRegion;
 use probe_rs::flashing::DownloadOptions;
 use probe_rs::MemoryInterface;
 use tbf_parser::parse::parse_tbf_header_lengths;
@@ -6,6 +9,7 @@ use tbf_parser::parse::parse_tbf_header_lengths;
 use crate::board_settings::BoardSettings;
 use crate::connection::{Connection, ProbeRSConnection};
 use crate::errors::{InternalError, TockloaderError};
+use crate::flash_geometry::FlashGeometry;
 use crate::tabs::tab::Tab;
 use crate::CommandInstall;
 
@@ -21,6 +25,7 @@ impl CommandInstall for ProbeRSConnection {
         }
         let session = self.session.as_mut().expect("Board must be open");
 
+        let scan_start = Instant::now();
         let mut core = session.core(self.target_info.core)?;
 
         // TODO(george-cosma): extract these informations without bootloader
@@ -75,86 +80,52 @@ impl CommandInstall for ProbeRSConnection {
         // At this point we no longer need to hold the probe-rs connection
         // to the core, as the flashing is done without it.
         drop(core);
-
-        // Make sure the binary is a multiple of the page size by padding 0xFFs
-
-        // TODO(george-cosma): check if the page-size differs + support
-        // multiple types of page sizes. Possibly make page size a board
-        // setting.
-        let page_size = 512;
-        let needs_padding = binary.len() % page_size != 0;
-
-        if needs_padding {
-            let remaining = page_size - (binary.len() % page_size);
-            dbg!(remaining);
-            for _i in 0..remaining {
-                binary.push(0xFF);
-            }
-        }
-
-        // Get indices of pages that have valid data to write
-        let mut valid_pages: Vec<u8> = Vec::new();
-        for i in 0..(size as usize / page_size) {
-            for b in binary[(i * page_size)..((i + 1) * page_size)]
-                .iter()
-                .copied()
-            {
-                if b != 0 {
-                    valid_pages.push(i.try_into().unwrap());
-                    break;
-                }
-            }
-        }
-
-        // If there are no pages valid, all pages would have been removed,
-        // so we write them all
-        if valid_pages.is_empty() {
-            for i in 0..(size as usize / page_size) {
-                valid_pages.push(i.try_into().unwrap());
-            }
-        }
-
-        // Include a blank page (if exists) after the end of a valid page.
-        // There might be a usable 0 on the next page
-        let mut ending_pages: Vec<u8> = Vec::new();
-        for &i in &valid_pages {
-            let mut iter = valid_pages.iter();
-            if !iter.any(|&x| x == (i + 1)) && (i + 1) < (size as usize / page_size) as u8 {
-                ending_pages.push(i + 1);
-            }
-        }
-
-        for i in ending_pages {
-            valid_pages.push(i);
+        log::info!("Scanned installed apps in {:?}", scan_start.elapsed());
+
+        // Make sure the binary ends on a page boundary by padding 0xFFs. The
+        // page geometry comes from the board settings, or else from the flash
+        // algorithm of the target.
+        let geometry = settings
+            .flash_geometry
+            .clone()
+            .unwrap_or_else(|| FlashGeometry::from_target(session.target()));
+        let end_address = geometry.align_up(new_address + binary.len() as u64);
+        binary.resize((end_address - new_address) as usize, 0xFF);
+
+        // Stage everything into a single loader, so that each sector is only
+        // erased and programmed once.
+        let stage_start = Instant::now();
+        let mut loader = session.target().flash_loader();
+        for page in geometry.pages_to_write(new_address, &binary) {
+            log::debug!("Staging page {:#x}..{:#x}", page.start, page.end);
+            let data =
+                &binary[(page.start - new_address) as usize..(page.end - new_address) as usize];
+            loader.add_data(page.start, data)?;
         }
 
-        for i in valid_pages {
-            println!("Writing page number {i}");
-            // Create the packet that we send to the bootloader. First four
-            // bytes are the address of the page
-            let mut pkt = Vec::new();
-
-            // Then the bytes that go into the page
-            for b in binary[(i as usize * page_size)..((i + 1) as usize * page_size)]
-                .iter()
-                .copied()
-            {
-                pkt.push(b);
-            }
-            let mut loader = session.target().flash_loader();
-
-            loader.add_data(
-                (new_address as u32 + (i as usize * page_size) as u32).into(),
-                &pkt,
-            )?;
-
-            let mut options = DownloadOptions::default();
-            options.keep_unwritten_bytes = true;
-
-            // Finally, the data can be programmed
-            // TODO(george-cosma): Can we move this outside the loop? Commit once?
-            loader.commit(session, options)?;
+        // An erased page marks the end of the apps list, unless the app ends
+        // with the flash.
+        let marker = geometry.page_at(end_address);
+        let in_flash = session.target().memory_map.iter().any(|region| {
+            matches!(region, MemoryRegion::Nvm(nvm)
+                if nvm.range.start <= marker.start && marker.end <= nvm.range.end)
+        });
+        if in_flash {
+            let erased = vec![0xFF; (marker.end - marker.start) as usize];
+            loader.add_data(marker.start, &erased)?;
         }
+        log::info!("Staged app data in {:?}", stage_start.elapsed());
+
+        let mut options = DownloadOptions::default();
+        options.keep_unwritten_bytes = true;
+
+        // Finally, the data can be programmed
+        let commit_start = Instant::now();
+        loader.commit(session, options)?;
+        log::info!(
+            "Erased and programmed flash in {:?}",
+            commit_start.elapsed()
+        );
 
         Ok(())
     }
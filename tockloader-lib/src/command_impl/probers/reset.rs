@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::connection::{Connection, ProbeRSConnection};
+use crate::errors::{InternalError, TockloaderError};
+use crate::{CommandReset, ResetMode};
+
+#[async_trait]
+impl CommandReset for ProbeRSConnection {
+    async fn reset(&mut self, mode: ResetMode) -> Result<(), TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let session = self.session.as_mut().expect("Board must be open");
+
+        let mut core = session.core(self.target_info.core)?;
+        match mode {
+            ResetMode::Run => core.reset()?,
+            ResetMode::Halt => {
+                core.reset_and_halt(Duration::from_millis(500))?;
+            }
+        }
+        Ok(())
+    }
+}
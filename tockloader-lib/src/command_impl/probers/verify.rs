@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use probe_rs::MemoryInterface;
+
+use crate::attributes::app_attributes::AppAttributes;
+use crate::connection::{Connection, ProbeRSConnection};
+use crate::credentials::keys::PublicKey;
+use crate::credentials::verify::{verify_footers, AppVerification};
+use crate::errors::{InternalError, TockloaderError};
+use crate::CommandVerify;
+
+#[async_trait]
+impl CommandVerify for ProbeRSConnection {
+    async fn verify_apps(
+        &mut self,
+        apps: &[AppAttributes],
+        keys: &[PublicKey],
+    ) -> Result<Vec<AppVerification>, TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let session = self.session.as_mut().expect("Board must be open");
+
+        let mut core = session.core(self.target_info.core)?;
+
+        let mut verifications = vec![];
+        for app in apps {
+            // The credentials cover everything from the start of the header to
+            // the end of the binary.
+            let mut integrity_region = vec![0u8; app.tbf_header.get_binary_end() as usize];
+            core.read(app.address, &mut integrity_region)?;
+
+            verifications.push(AppVerification {
+                address: app.address,
+                credentials: verify_footers(&integrity_region, &app.tbf_footers, keys),
+            });
+        }
+
+        Ok(verifications)
+    }
+}
@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+
+use crate::attributes::decode::{
+    decode_attributes, encode_attribute, find_attribute, find_attribute_slot, slot_for_attribute,
+    DecodedAttribute, ATTRIBUTE_SIZE, ATTRIBUTE_SLOTS, EMPTY_ATTRIBUTE,
+};
+use crate::bootloader_serial::{
+    issue_command, ping_bootloader_and_wait_for_response, Command, Response,
+};
+use crate::connection::{Connection, SerialConnection};
+use crate::errors::{InternalError, TockError, TockloaderError};
+use crate::CommandAttributes;
+
+impl SerialConnection {
+    /// Read and decode every attribute slot, one `GetAttribute` command per
+    /// slot.
+    async fn read_attributes(&mut self) -> Result<Vec<(usize, DecodedAttribute)>, TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        ping_bootloader_and_wait_for_response(stream).await?;
+
+        let mut data = Vec::with_capacity(ATTRIBUTE_SIZE * ATTRIBUTE_SLOTS);
+        for slot in 0..ATTRIBUTE_SLOTS {
+            let pkt = vec![slot as u8];
+            let (_, slot_data) = issue_command(
+                stream,
+                Command::GetAttribute,
+                pkt,
+                true,
+                ATTRIBUTE_SIZE,
+                Response::GetAttribute,
+            )
+            .await?;
+            data.extend_from_slice(&slot_data);
+        }
+        Ok(decode_attributes(&data))
+    }
+
+    /// Overwrite a single attribute slot with the `SetAttribute` command.
+    async fn write_attribute_slot(
+        &mut self,
+        slot: usize,
+        data: &[u8; ATTRIBUTE_SIZE],
+    ) -> Result<(), TockloaderError> {
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        let mut pkt = vec![slot as u8];
+        pkt.extend_from_slice(data);
+        let (_, _) =
+            issue_command(stream, Command::SetAttribute, pkt, true, 0, Response::OK).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CommandAttributes for SerialConnection {
+    async fn get_attribute(&mut self, key: &str) -> Result<String, TockloaderError> {
+        let attributes = self.read_attributes().await?;
+        find_attribute(&attributes, key)
+            .map(str::to_string)
+            .ok_or_else(|| TockError::MissingAttribute(key.to_string()).into())
+    }
+
+    async fn set_attribute(&mut self, key: &str, value: &str) -> Result<(), TockloaderError> {
+        let data = encode_attribute(key, value)?;
+        let attributes = self.read_attributes().await?;
+        let slot = slot_for_attribute(&attributes, key)?;
+        self.write_attribute_slot(slot, &data).await
+    }
+
+    async fn remove_attribute(&mut self, key: &str) -> Result<(), TockloaderError> {
+        let attributes = self.read_attributes().await?;
+        let slot = find_attribute_slot(&attributes, key)?;
+        self.write_attribute_slot(slot, &EMPTY_ATTRIBUTE).await
+    }
+}
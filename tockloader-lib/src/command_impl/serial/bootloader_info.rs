@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+
+use crate::attributes::bootloader_info::{read_board_id_serial, BootloaderInfo};
+use crate::bootloader_serial::ping_bootloader_and_wait_for_response;
+use crate::connection::{Connection, SerialConnection};
+use crate::errors::{InternalError, TockloaderError};
+use crate::CommandBootloaderInfo;
+
+#[async_trait]
+impl CommandBootloaderInfo for SerialConnection {
+    async fn bootloader_info(&mut self) -> Result<BootloaderInfo, TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        ping_bootloader_and_wait_for_response(stream).await?;
+
+        BootloaderInfo::read_serial(stream).await
+    }
+
+    async fn board_id(&mut self) -> Result<Vec<u8>, TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        ping_bootloader_and_wait_for_response(stream).await?;
+
+        read_board_id_serial(stream).await
+    }
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+
+use crate::bootloader_serial::{
+    bootloader_address, issue_command, ping_bootloader_and_wait_for_response, BootloaderPort,
+    Command, Response,
+};
+use crate::connection::{Connection, SerialConnection};
+use crate::errors::{InternalError, TockError, TockloaderError};
+use crate::external_flash::{check_page_aligned, erase_units, EraseUnit, EXTERNAL_PAGE_SIZE};
+use crate::read_cache::READ_CHUNK_SIZE;
+use crate::CommandExternalFlash;
+
+impl SerialConnection {
+    /// Check that the bootloader answers, and get it to initialize the
+    /// external flash.
+    async fn open_external_flash(&mut self) -> Result<&mut BootloaderPort, TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        ping_bootloader_and_wait_for_response(stream).await?;
+        issue_command(stream, Command::XFinit, vec![], true, 0, Response::OK).await?;
+
+        Ok(stream)
+    }
+}
+
+/// Compare the CRC the bootloader computes over the external flash at
+/// `address` with the CRC of `data`.
+async fn check_external_crc(
+    port: &mut BootloaderPort,
+    address: u64,
+    data: &[u8],
+) -> Result<(), TockloaderError> {
+    let mut pkt = (address as u32).to_le_bytes().to_vec();
+    pkt.extend((data.len() as u32).to_le_bytes());
+
+    let (_, buf) = issue_command(port, Command::Crcef, pkt, true, 4, Response::Crcxf).await?;
+    let found = u32::from_le_bytes(buf[..4].try_into().expect("response is 4 bytes long"));
+    let expected = crc32fast::hash(data);
+    if found != expected {
+        return Err(TockError::ExternalFlashCrcMismatch {
+            address,
+            expected,
+            found,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl CommandExternalFlash for SerialConnection {
+    async fn read_external(
+        &mut self,
+        address: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, TockloaderError> {
+        let stream = self.open_external_flash().await?;
+
+        let mut data = Vec::with_capacity(length);
+        while data.len() < length {
+            let chunk = (length - data.len()).min(READ_CHUNK_SIZE);
+            let mut pkt = bootloader_address(address + data.len() as u64)?
+                .to_le_bytes()
+                .to_vec();
+            pkt.extend((chunk as u16).to_le_bytes());
+
+            let (_, buf) = issue_command(
+                stream,
+                Command::XRRange,
+                pkt,
+                true,
+                chunk,
+                Response::XRRange,
+            )
+            .await?;
+            data.extend(buf);
+        }
+
+        check_external_crc(stream, address, &data).await?;
+        Ok(data)
+    }
+
+    async fn write_external(&mut self, address: u64, data: &[u8]) -> Result<(), TockloaderError> {
+        check_page_aligned(address)?;
+        let stream = self.open_external_flash().await?;
+
+        let page_size = EXTERNAL_PAGE_SIZE as usize;
+        let mut padded = data.to_vec();
+        padded.resize(data.len().div_ceil(page_size) * page_size, 0xFF);
+
+        for (i, page) in padded.chunks(page_size).enumerate() {
+            // First four bytes are the address of the page
+            let mut pkt = (address as u32 + (i * page_size) as u32)
+                .to_le_bytes()
+                .to_vec();
+            // Then the bytes that go into the page
+            pkt.extend(page);
+
+            issue_command(stream, Command::XWPage, pkt, true, 0, Response::OK).await?;
+        }
+
+        check_external_crc(stream, address, &padded).await
+    }
+
+    async fn erase_external(&mut self, address: u64, length: u64) -> Result<(), TockloaderError> {
+        check_page_aligned(address)?;
+        check_page_aligned(length)?;
+        let stream = self.open_external_flash().await?;
+
+        for unit in erase_units(address..address + length) {
+            let (command, address) = match unit {
+                EraseUnit::Page(address) => (Command::XEPage, address),
+                EraseUnit::Block(address) => (Command::XEBlock, address),
+            };
+            let pkt = (address as u32).to_le_bytes().to_vec();
+            issue_command(stream, command, pkt, true, 0, Response::OK).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use crate::connection::SerialConnection;
+use crate::errors::{TockError, TockloaderError};
+use crate::CommandFlashBootloader;
+
+#[async_trait]
+impl CommandFlashBootloader for SerialConnection {
+    async fn flash_bootloader(
+        &mut self,
+        _binary: &[u8],
+        _address: u64,
+        _force: bool,
+    ) -> Result<(), TockloaderError> {
+        // The Tock bootloader refuses writes to its own flash region and has
+        // no command to replace itself, so there is no self-update path yet.
+        Err(TockError::BootloaderSelfUpdateUnsupported.into())
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+
+use crate::attributes::system_attributes::SystemAttributes;
+use crate::board_settings::BoardSettings;
+use crate::bootloader_serial::{ping_bootloader_and_wait_for_response, write_pages};
+use crate::connection::{Connection, SerialConnection};
+use crate::errors::{InternalError, TockloaderError};
+use crate::kernel::KernelImage;
+use crate::CommandFlashKernel;
+
+#[async_trait]
+impl CommandFlashKernel for SerialConnection {
+    async fn flash_kernel(
+        &mut self,
+        settings: &BoardSettings,
+        kernel: &KernelImage,
+        force: bool,
+    ) -> Result<(), TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        ping_bootloader_and_wait_for_response(stream).await?;
+
+        let system = match SystemAttributes::read_system_attributes_serial(stream).await {
+            Ok(system) => Some(system),
+            Err(e) => {
+                log::info!("Could not read system attributes ({e}), using board settings.");
+                None
+            }
+        };
+
+        let address = kernel.placement(system.as_ref(), settings, force)?;
+
+        let geometry = settings.flash_geometry.clone().unwrap_or_default();
+        if geometry.page_at(address).start != address {
+            return Err(InternalError::MisconfiguredBoardSettings(format!(
+                "kernel address {address:#x} is not aligned to a flash page"
+            ))
+            .into());
+        }
+
+        let mut binary = kernel.data.clone();
+        let end_address = geometry.align_up(address + binary.len() as u64);
+        binary.resize((end_address - address) as usize, 0xFF);
+
+        log::info!("Writing {} byte kernel at {address:#x}", kernel.data.len());
+
+        // Unlike apps, every page is written: blank kernel pages still have to
+        // replace whatever was there before.
+        write_pages(stream, address, &binary).await?;
+
+        Ok(())
+    }
+}
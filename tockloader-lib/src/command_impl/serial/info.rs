@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 
 use crate::attributes::app_attributes::AppAttributes;
+use crate::attributes::bootloader_info::{read_board_id_serial, BootloaderInfo};
 use crate::attributes::general_attributes::GeneralAttributes;
 use crate::attributes::system_attributes::SystemAttributes;
 use crate::board_settings::BoardSettings;
@@ -26,6 +27,27 @@ impl CommandInfo for SerialConnection {
         let app_attributes =
             AppAttributes::read_apps_data_serial(stream, settings.start_address).await?;
 
-        Ok(GeneralAttributes::new(system_attributes, app_attributes))
+        let mut attributes = GeneralAttributes::new(system_attributes, app_attributes);
+
+        // Older bootloaders do not implement these commands, which is not a
+        // reason to fail the whole query.
+        attributes.bootloader = match BootloaderInfo::read_serial(stream).await {
+            Ok(info) => Some(info),
+            Err(TockloaderError::Tock(e)) => {
+                log::debug!("Bootloader info unavailable: {e}");
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        attributes.board_id = match read_board_id_serial(stream).await {
+            Ok(id) => Some(id),
+            Err(TockloaderError::Tock(e)) => {
+                log::debug!("Board ID unavailable: {e}");
+                None
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(attributes)
     }
 }
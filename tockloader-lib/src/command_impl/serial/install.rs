@@ -2,11 +2,12 @@ use async_trait::async_trait;
 
 use crate::attributes::system_attributes::SystemAttributes;
 use crate::board_settings::BoardSettings;
-use crate::bootloader_serial::{issue_command, Command, Response};
+use crate::bootloader_serial::{issue_command, write_pages, Command, Response};
 use crate::connection::Connection;
 use crate::connection::SerialConnection;
 use crate::errors::InternalError;
 use crate::errors::TockloaderError;
+use crate::read_cache::ReadCache;
 use crate::tabs::tab::Tab;
 use crate::CommandInstall;
 use tbf_parser::parse::parse_tbf_header_lengths;
@@ -33,10 +34,6 @@ impl CommandInstall for SerialConnection {
             .ok_or("No board name found.".to_owned())
             .map_err(|e| TockloaderError::Internal(InternalError::MisconfiguredBoardSettings(e)))?;
         //TODO: handle the case when board is not set
-        let kernel_version = system_attributes
-            .kernel_version
-            .ok_or("No kernel version found.".to_owned())
-            .map_err(|e| TockloaderError::Internal(InternalError::MisconfiguredBoardSettings(e)))?;
 
         if tab_file.is_compatible_with_board(&board) {
             log::info!("Specified tab is compatible with board.");
@@ -44,10 +41,14 @@ impl CommandInstall for SerialConnection {
             //TODO: replace with appropriate error
             panic!("Specified tab is not compatible with board.");
         }
-        if tab_file.is_compatible_with_kernel_verison(kernel_version as u32) {
-            log::info!("Specified tab is compatible with your kernel version.");
-        } else {
-            log::info!("Specified tab is not compatible with your kernel version.");
+        match system_attributes.kernel_version {
+            Some(kernel_version)
+                if tab_file.is_compatible_with_kernel_verison(kernel_version as u32) =>
+            {
+                log::info!("Specified tab is compatible with your kernel version.");
+            }
+            Some(_) => log::info!("Specified tab is not compatible with your kernel version."),
+            None => log::info!("No kernel attributes found, skipping the kernel version check."),
         }
 
         let mut address = match system_attributes.appaddr {
@@ -63,35 +64,10 @@ impl CommandInstall for SerialConnection {
             }
         };
 
+        // Skip over the installed apps to find the end of the list.
+        let mut cache = ReadCache::new(&mut *stream);
         loop {
-            // Read a block of 200 8-bit words
-            let mut pkt = (address as u32).to_le_bytes().to_vec();
-            let length = (200_u16).to_le_bytes().to_vec();
-            pkt.extend(length);
-
-            let (_, message) = issue_command(
-                stream,
-                Command::ReadRange,
-                pkt,
-                true,
-                200,
-                Response::ReadRange,
-            )
-            .await?;
-
-            // TODO: handle error properly
-
-            // let (ver, header_len, whole_len) = parse_tbf_header_lengths(
-            //     &message[0..8]
-            //         .try_into()
-            //         .expect("Buffer length must be at least 8 bytes long."),
-            //     //TODO: select an appropriate error
-            // )
-            // .unwrap();
-
-            // if header_len == 0 {
-            //     break; // No more apps
-            // }
+            let message = cache.read(address, 8).await?;
 
             let (_ver, _header_len, whole_len) = match parse_tbf_header_lengths(
                 &message[0..8]
@@ -124,59 +100,18 @@ impl CommandInstall for SerialConnection {
             (address, 0)
         };
 
-        // Make sure the binary is a multiple of the page size by padding 0xFFs
-        // TODO(Micu Ana): check if the page-size differs
-        let page_size = 512;
-
-        let remaining = page_size - (binary.len() % page_size);
-        let padding = vec![0xFF; remaining % page_size];
-        binary.extend(padding);
-
-        let binary_len = binary.len();
-
-        // Get indices of pages that have valid data to write
-        let mut valid_pages: Vec<u8> = Vec::new();
-
-        valid_pages.extend(
-            (0..(binary_len / page_size))
-                .filter(|&i| {
-                    binary[(i * page_size)..((i + 1) * page_size)]
-                        .iter()
-                        .any(|&b| b != 0)
-                })
-                .map(|i| i as u8),
-        );
-
-        // If there are no pages valid, all pages would have been removed, so we write them all
-        // Fallback that ensures old data is cleared and that aren't any partially written apps
-        if valid_pages.is_empty() {
-            valid_pages.extend((0..(binary_len / page_size)).map(|i| i as u8));
-        }
-
-        // Include a blank page (if exists) after the end of a valid page. There might be a usable 0 on the next page
-        let existing_pages = valid_pages.clone();
-
-        let ending_pages = existing_pages.iter().map(|&i| i + 1).filter(|&next| {
-            next < (binary_len / page_size) as u8 && !existing_pages.contains(&next)
-        });
-
-        valid_pages.extend(ending_pages);
-
-        for i in valid_pages {
-            // Create the packet that we send to the bootloader
-            // First four bytes are the address of the page
-            let mut pkt = (new_address as u32 + (i as usize * page_size) as u32)
-                .to_le_bytes()
-                .to_vec();
-            // Then the bytes that go into the page
-            pkt.extend(&binary[(i as usize * page_size)..((i + 1) as usize * page_size)]);
+        // Make sure the binary ends on a page boundary by padding 0xFFs
+        let geometry = settings.flash_geometry.clone().unwrap_or_default();
+        let end_address = geometry.align_up(new_address + binary.len() as u64);
+        binary.resize((end_address - new_address) as usize, 0xFF);
 
-            // Write to bootloader
-            let (_, _) =
-                issue_command(stream, Command::WritePage, pkt, true, 0, Response::OK).await?;
+        for page in geometry.pages_to_write(new_address, &binary) {
+            let data =
+                &binary[(page.start - new_address) as usize..(page.end - new_address) as usize];
+            write_pages(stream, page.start, data).await?;
         }
 
-        new_address += binary.len() as u64;
+        new_address = end_address;
 
         let pkt = (new_address as u32).to_le_bytes().to_vec();
 
@@ -1,4 +1,11 @@
+pub mod attributes;
+pub mod bootloader_info;
 pub mod erase_apps;
+pub mod external_flash;
+pub mod flash_bootloader;
+pub mod flash_kernel;
 pub mod info;
 pub mod install;
 pub mod list;
+pub mod reset;
+pub mod verify;
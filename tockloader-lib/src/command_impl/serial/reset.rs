@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+
+use crate::bootloader_serial::{exit_bootloader, toggle_bootloader_entry_dtr_rts};
+use crate::connection::{Connection, SerialConnection};
+use crate::errors::{InternalError, TockloaderError};
+use crate::{CommandReset, ResetMode};
+
+#[async_trait]
+impl CommandReset for SerialConnection {
+    async fn reset(&mut self, mode: ResetMode) -> Result<(), TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        match mode {
+            ResetMode::Run => exit_bootloader(stream).await,
+            ResetMode::Halt => toggle_bootloader_entry_dtr_rts(&mut stream.stream).await,
+        }
+    }
+}
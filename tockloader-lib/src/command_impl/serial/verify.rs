@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+
+use crate::attributes::app_attributes::AppAttributes;
+use crate::bootloader_serial::ping_bootloader_and_wait_for_response;
+use crate::connection::{Connection, SerialConnection};
+use crate::credentials::keys::PublicKey;
+use crate::credentials::verify::{verify_footers, AppVerification};
+use crate::errors::{InternalError, TockloaderError};
+use crate::read_cache::ReadCache;
+use crate::CommandVerify;
+
+#[async_trait]
+impl CommandVerify for SerialConnection {
+    async fn verify_apps(
+        &mut self,
+        apps: &[AppAttributes],
+        keys: &[PublicKey],
+    ) -> Result<Vec<AppVerification>, TockloaderError> {
+        if !self.is_open() {
+            return Err(InternalError::ConnectionNotOpen.into());
+        }
+        let stream = self.stream.as_mut().expect("Board must be open");
+
+        ping_bootloader_and_wait_for_response(stream).await?;
+
+        // Applications are larger than what a single command can return, so
+        // they are read in chunks through the cache.
+        let mut cache = ReadCache::new(&mut *stream);
+        let mut verifications = vec![];
+        for app in apps {
+            // The credentials cover everything from the start of the header to
+            // the end of the binary.
+            let binary_end = app.tbf_header.get_binary_end();
+            let integrity_region = cache.read(app.address, binary_end as usize).await?;
+
+            verifications.push(AppVerification {
+                address: app.address,
+                credentials: verify_footers(&integrity_region, &app.tbf_footers, keys),
+            });
+        }
+
+        Ok(verifications)
+    }
+}
@@ -1,21 +1,41 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
-use probe_rs::probe::DebugProbeInfo;
+use probe_rs::probe::{DebugProbeInfo, WireProtocol};
 use probe_rs::{Permissions, Session};
 use tokio::io::AsyncWriteExt;
 use tokio_serial::{FlowControl, Parity, SerialPort, SerialStream, StopBits};
 
+use crate::bootloader_serial::BootloaderPort;
 use crate::errors::TockloaderError;
 use log::info;
 pub struct ProbeTargetInfo {
     pub chip: String,
     pub core: usize,
+    /// Protocol used to talk to the chip. The probe picks one, usually SWD,
+    /// when this is `None`.
+    pub protocol: Option<WireProtocol>,
+    /// Clock speed of the probe in kHz. The probe default is used when this
+    /// is `None`.
+    pub speed_khz: Option<u32>,
+    /// Hold the reset line while attaching, for chips that do not answer the
+    /// debug port otherwise, such as when they sleep or remap the debug pins.
+    pub connect_under_reset: bool,
+    /// Let probe-rs mass erase a locked chip to unlock it. This destroys
+    /// everything stored on the chip.
+    pub allow_erase_all: bool,
 }
 
 impl ProbeTargetInfo {
     pub fn default(chip: String) -> Self {
-        Self { chip, core: 0 }
+        Self {
+            chip,
+            core: 0,
+            protocol: None,
+            speed_khz: None,
+            connect_under_reset: false,
+            allow_erase_all: false,
+        }
     }
 }
 
@@ -27,6 +47,7 @@ pub struct SerialTargetInfo {
     pub timeout: Duration,
     pub request_to_send: bool,
     pub data_terminal_ready: bool,
+    pub retry: RetryPolicy,
 }
 
 impl Default for SerialTargetInfo {
@@ -39,6 +60,30 @@ impl Default for SerialTargetInfo {
             timeout: Duration::from_millis(500),
             request_to_send: false,
             data_terminal_ready: false,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// How bootloader commands recover from transient errors, such as a timeout
+/// or a garbled response, over a serial connection. Errors reported by the
+/// bootloader itself, such as a bad address, are never retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Number of times a command is sent again after a transient error.
+    pub retries: u32,
+    /// How long to wait for the response to a command.
+    pub timeout: Duration,
+    /// Drop pending input and ping the bootloader again before retrying.
+    pub resync: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            timeout: Duration::from_millis(5000),
+            resync: true,
         }
     }
 }
@@ -75,9 +120,29 @@ impl ProbeRSConnection {
 #[async_trait]
 impl Connection for ProbeRSConnection {
     async fn open(&mut self) -> Result<(), TockloaderError> {
-        let probe = self.debug_probe.open()?;
+        let mut probe = self.debug_probe.open()?;
+
+        if let Some(protocol) = self.target_info.protocol {
+            probe.select_protocol(protocol)?;
+        }
+        if let Some(speed_khz) = self.target_info.speed_khz {
+            let actual = probe.set_speed(speed_khz)?;
+            if actual != speed_khz {
+                info!("Probe speed set to {actual} kHz instead of {speed_khz} kHz.");
+            }
+        }
+
+        let mut permissions = Permissions::default();
+        if self.target_info.allow_erase_all {
+            permissions = permissions.allow_erase_all();
+        }
 
-        self.session = Some(probe.attach(&self.target_info.chip, Permissions::default())?);
+        let chip = self.target_info.chip.as_str();
+        self.session = Some(if self.target_info.connect_under_reset {
+            probe.attach_under_reset(chip, permissions)?
+        } else {
+            probe.attach(chip, permissions)?
+        });
         info!("ProbeRS connection opened succesfully.");
 
         Ok(())
@@ -96,7 +161,7 @@ impl Connection for ProbeRSConnection {
 }
 
 pub struct SerialConnection {
-    pub(crate) stream: Option<SerialStream>,
+    pub(crate) stream: Option<BootloaderPort>,
     /// Used both to open new connections but also used during the session to
     /// provide information about the target
     pub(crate) target_info: SerialTargetInfo,
@@ -113,7 +178,7 @@ impl SerialConnection {
         }
     }
     pub fn into_inner_stream(self) -> Option<SerialStream> {
-        self.stream
+        self.stream.map(|port| port.stream)
     }
 }
 
@@ -131,14 +196,17 @@ impl Connection for SerialConnection {
         stream.write_request_to_send(self.target_info.request_to_send)?;
         stream.write_data_terminal_ready(self.target_info.data_terminal_ready)?;
 
-        self.stream = Some(stream);
+        self.stream = Some(BootloaderPort {
+            stream,
+            retry: self.target_info.retry.clone(),
+        });
         info!("Serial connection opened succesfully.");
         Ok(())
     }
 
     async fn close(&mut self) -> Result<(), TockloaderError> {
-        if let Some(mut stream) = self.stream.take() {
-            stream
+        if let Some(mut port) = self.stream.take() {
+            port.stream
                 .shutdown()
                 .await
                 .map_err(|e| TockloaderError::Serial(e.into()))?;
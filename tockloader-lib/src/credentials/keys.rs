@@ -0,0 +1,163 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::path::Path;
+
+use p256::ecdsa::{SigningKey, VerifyingKey};
+use p256::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::{BigUint, RsaPrivateKey, RsaPublicKey};
+
+use crate::errors::{CredentialsError, TockloaderError};
+
+/// Public exponent used by the RSA credentials footers, which only store the
+/// modulus of the key.
+pub(crate) const RSA_PUBLIC_EXPONENT: u32 = 65537;
+
+/// A public key used to check the signature of an application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKey {
+    Rsa(RsaPublicKey),
+    EcdsaNistP256(VerifyingKey),
+}
+
+impl PublicKey {
+    /// Load a public key from a file. See [`PublicKey::from_bytes`] for the
+    /// supported formats.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TockloaderError> {
+        let bytes = std::fs::read(path).map_err(CredentialsError::IO)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Load a public key from its encoded form.
+    ///
+    /// Supported formats are:
+    /// - PEM or DER SubjectPublicKeyInfo, for both RSA and ECDSA P-256 keys
+    ///   (the output of `openssl pkey -pubout`)
+    /// - PEM or DER PKCS#1 RSA public keys
+    /// - SEC1 encoded ECDSA P-256 points
+    /// - raw 3072 or 4096 bit big-endian RSA modulus, the format stored in the
+    ///   credentials footers, with a public exponent of 65537
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TockloaderError> {
+        if let Ok(pem) = std::str::from_utf8(bytes) {
+            if pem.trim_start().starts_with("-----BEGIN") {
+                return Self::from_pem(pem);
+            }
+        }
+
+        if let Ok(key) = RsaPublicKey::from_public_key_der(bytes) {
+            return Ok(PublicKey::Rsa(key));
+        }
+        if let Ok(key) = RsaPublicKey::from_pkcs1_der(bytes) {
+            return Ok(PublicKey::Rsa(key));
+        }
+        if let Ok(key) = VerifyingKey::from_public_key_der(bytes) {
+            return Ok(PublicKey::EcdsaNistP256(key));
+        }
+        if let Ok(key) = VerifyingKey::from_sec1_bytes(bytes) {
+            return Ok(PublicKey::EcdsaNistP256(key));
+        }
+        if bytes.len() == 384 || bytes.len() == 512 {
+            return rsa_from_modulus(bytes).map(PublicKey::Rsa);
+        }
+
+        Err(CredentialsError::InvalidKey("unrecognized public key format".to_owned()).into())
+    }
+
+    fn from_pem(pem: &str) -> Result<Self, TockloaderError> {
+        if let Ok(key) = RsaPublicKey::from_public_key_pem(pem) {
+            return Ok(PublicKey::Rsa(key));
+        }
+        if let Ok(key) = RsaPublicKey::from_pkcs1_pem(pem) {
+            return Ok(PublicKey::Rsa(key));
+        }
+        if let Ok(key) = VerifyingKey::from_public_key_pem(pem) {
+            return Ok(PublicKey::EcdsaNistP256(key));
+        }
+
+        Err(CredentialsError::InvalidKey("unrecognized PEM public key".to_owned()).into())
+    }
+}
+
+/// A private key used to sign applications.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum PrivateKey {
+    Rsa(RsaPrivateKey),
+    EcdsaNistP256(SigningKey),
+}
+
+impl PrivateKey {
+    /// Load a private key from a file. See [`PrivateKey::from_bytes`] for the
+    /// supported formats.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TockloaderError> {
+        let bytes = std::fs::read(path).map_err(CredentialsError::IO)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Load a private key from its encoded form.
+    ///
+    /// Supported formats are:
+    /// - PEM or DER PKCS#8, for both RSA and ECDSA P-256 keys (the output of
+    ///   `openssl genpkey`)
+    /// - PEM or DER PKCS#1 RSA private keys (`openssl genrsa -traditional`)
+    /// - PEM or DER SEC1 ECDSA P-256 private keys (`openssl ecparam -genkey`)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TockloaderError> {
+        if let Ok(pem) = std::str::from_utf8(bytes) {
+            if pem.trim_start().starts_with("-----BEGIN") {
+                return Self::from_pem(pem);
+            }
+        }
+
+        if let Ok(key) = RsaPrivateKey::from_pkcs8_der(bytes) {
+            return Ok(PrivateKey::Rsa(key));
+        }
+        if let Ok(key) = RsaPrivateKey::from_pkcs1_der(bytes) {
+            return Ok(PrivateKey::Rsa(key));
+        }
+        if let Ok(key) = SigningKey::from_pkcs8_der(bytes) {
+            return Ok(PrivateKey::EcdsaNistP256(key));
+        }
+        if let Ok(key) = p256::SecretKey::from_sec1_der(bytes) {
+            return Ok(PrivateKey::EcdsaNistP256(key.into()));
+        }
+
+        Err(CredentialsError::InvalidKey("unrecognized private key format".to_owned()).into())
+    }
+
+    fn from_pem(pem: &str) -> Result<Self, TockloaderError> {
+        if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(pem) {
+            return Ok(PrivateKey::Rsa(key));
+        }
+        if let Ok(key) = RsaPrivateKey::from_pkcs1_pem(pem) {
+            return Ok(PrivateKey::Rsa(key));
+        }
+        if let Ok(key) = SigningKey::from_pkcs8_pem(pem) {
+            return Ok(PrivateKey::EcdsaNistP256(key));
+        }
+        if let Ok(key) = p256::SecretKey::from_sec1_pem(pem) {
+            return Ok(PrivateKey::EcdsaNistP256(key.into()));
+        }
+
+        Err(CredentialsError::InvalidKey("unrecognized PEM private key".to_owned()).into())
+    }
+
+    /// The public half of this key, which can be used to verify the
+    /// credentials it creates.
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            PrivateKey::Rsa(key) => PublicKey::Rsa(key.to_public_key()),
+            PrivateKey::EcdsaNistP256(key) => PublicKey::EcdsaNistP256(*key.verifying_key()),
+        }
+    }
+}
+
+/// Build an RSA key from the big-endian modulus stored in a credentials footer.
+pub(crate) fn rsa_from_modulus(modulus: &[u8]) -> Result<RsaPublicKey, TockloaderError> {
+    RsaPublicKey::new(
+        BigUint::from_bytes_be(modulus),
+        BigUint::from(RSA_PUBLIC_EXPONENT),
+    )
+    .map_err(|e| CredentialsError::InvalidKey(e.to_string()).into())
+}
@@ -0,0 +1,88 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+//! Application credentials, stored in the `TbfFooterCredentials` footers of a
+//! TBF.
+//!
+//! See also <https://github.com/tock/tock/blob/master/doc/reference/trd-appid.md>
+
+pub mod keys;
+pub mod sign;
+pub mod verify;
+
+use std::ops::Range;
+
+use tbf_parser::parse::{parse_tbf_footers, parse_tbf_header, parse_tbf_header_lengths};
+use tbf_parser::types::{TbfFooterTlv, TbfHeader};
+
+use crate::attributes::app_attributes::TbfFooter;
+use crate::errors::{TabError, TockloaderError};
+
+/// Size of the type and length fields at the start of every footer TLV.
+const TLV_HEADER_SIZE: usize = 4;
+
+/// A TBF binary split into the parts covered by credentials.
+pub(crate) struct ParsedTbf<'a> {
+    pub(crate) header: TbfHeader,
+    /// Everything from the start of the header to the end of the binary. This
+    /// is what credentials are computed over.
+    pub(crate) integrity_region: &'a [u8],
+    /// Every credentials footer, along with the offset of its TLV in the TBF.
+    /// Empty for padding.
+    pub(crate) footers: Vec<(usize, TbfFooter)>,
+    /// Footers of a type other than credentials, as the range of the TBF
+    /// holding their TLV.
+    pub(crate) unknown_footers: Vec<Range<usize>>,
+    pub(crate) total_size: usize,
+}
+
+pub(crate) fn parse_tbf(tbf: &[u8]) -> Result<ParsedTbf<'_>, TockloaderError> {
+    let invalid = || TabError::InvalidTbf("TBF".to_owned());
+
+    let lengths: &[u8; 8] = tbf
+        .get(0..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(invalid)?;
+    let (version, header_size, total_size) =
+        parse_tbf_header_lengths(lengths).map_err(|_| invalid())?;
+
+    let header_data = tbf.get(0..header_size as usize).ok_or_else(invalid)?;
+    let header = parse_tbf_header(header_data, version).map_err(TabError::Parsing)?;
+
+    let binary_end = header.get_binary_end() as usize;
+    let total_size = total_size as usize;
+    if binary_end > total_size || total_size > tbf.len() {
+        return Err(invalid().into());
+    }
+
+    let mut footers = vec![];
+    let mut unknown_footers = vec![];
+    if let TbfHeader::TbfHeaderV2(_) = header {
+        for footer in parse_tbf_footers(&tbf[binary_end..total_size]) {
+            match footer.map_err(TabError::Parsing)? {
+                TbfFooterTlv::Credentials {
+                    offset,
+                    length,
+                    credentials,
+                } => footers.push((
+                    binary_end + offset,
+                    TbfFooter::new(credentials, length.into()),
+                )),
+                TbfFooterTlv::Unknown { offset, data, .. } => {
+                    let start = binary_end + offset;
+                    unknown_footers.push(start..start + TLV_HEADER_SIZE + data.len());
+                }
+                TbfFooterTlv::Padding { .. } => {}
+            }
+        }
+    }
+
+    Ok(ParsedTbf {
+        header,
+        integrity_region: &tbf[..binary_end],
+        footers,
+        unknown_footers,
+        total_size,
+    })
+}
@@ -0,0 +1,269 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::ops::Range;
+
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::Signature;
+use rsa::traits::PublicKeyParts;
+use rsa::Pkcs1v15Sign;
+use sha2::{Digest, Sha512};
+use tbf_parser::types::{TbfFooterV2Credentials, TbfFooterV2CredentialsType, TbfHeaderTypes};
+
+use crate::credentials::keys::PrivateKey;
+use crate::credentials::{parse_tbf, TLV_HEADER_SIZE};
+use crate::errors::{CredentialsError, TockloaderError};
+
+/// Size of the format field at the start of every credentials footer.
+const FORMAT_SIZE: usize = 4;
+
+/// Smallest footer that can hold reserved space: the TLV header and the
+/// format.
+const MIN_RESERVED_FOOTER_SIZE: usize = TLV_HEADER_SIZE + FORMAT_SIZE;
+
+/// Add a credentials footer, signed with `key`, to a TBF binary.
+///
+/// ECDSA P-256 keys create an `EcdsaNistP256` footer, RSA keys of 3072 or 4096
+/// bits create an `Rsa3072Key` or `Rsa4096Key` footer. The signature covers
+/// the header and the binary, up to `binary_end_offset`, so the TBF must have
+/// a Program header.
+///
+/// Existing credentials footers are kept. The new footer takes its space from
+/// the reserved footers, which are merged into a single one after it. The
+/// header is left untouched, so signing fails if there is not enough reserved
+/// space: growing `total_size` would invalidate the existing credentials.
+///
+/// Returns the signed TBF, with the same size as `tbf`.
+pub fn sign_tbf(tbf: &[u8], key: &PrivateKey) -> Result<Vec<u8>, TockloaderError> {
+    let parsed = parse_tbf(tbf)?;
+    if !parsed.header.is_app() {
+        return Err(signing_error("padding cannot be signed"));
+    }
+
+    let header_size = parsed.header.header_size() as usize;
+    if !has_program_header(&tbf[..header_size]) {
+        return Err(signing_error(
+            "the TBF has no Program header, so it cannot hold footers",
+        ));
+    }
+
+    let binary_end = parsed.integrity_region.len();
+
+    // Keep every existing footer byte for byte, in order, and drop the
+    // reserved ones: the remaining space is reserved again after the new
+    // footer.
+    let mut kept_ranges: Vec<Range<usize>> = parsed
+        .footers
+        .iter()
+        .filter(|(_, footer)| !matches!(footer.credentials, TbfFooterV2Credentials::Reserved(_)))
+        .map(|(offset, footer)| *offset..*offset + TLV_HEADER_SIZE + footer.size as usize)
+        .chain(parsed.unknown_footers.iter().cloned())
+        .collect();
+    kept_ranges.sort_by_key(|range| range.start);
+    let kept_footers: Vec<u8> = kept_ranges
+        .into_iter()
+        .flat_map(|range| tbf[range].iter().copied())
+        .collect();
+
+    let (format, data_size) = credentials_layout(key)?;
+    let footer_size = TLV_HEADER_SIZE + FORMAT_SIZE + data_size;
+
+    let used = binary_end + kept_footers.len() + footer_size;
+    let total_size = parsed.total_size;
+    let reserved = total_size.saturating_sub(used);
+    if used > total_size || (reserved != 0 && reserved < MIN_RESERVED_FOOTER_SIZE) {
+        return Err(CredentialsError::Signing(format!(
+            "not enough reserved footer space for a {footer_size} byte footer, rebuild the app with `elf2tab --minimum-footer-size`"
+        ))
+        .into());
+    }
+
+    let mut signed = tbf[..binary_end].to_vec();
+    let signature = sign(&signed, key);
+
+    signed.extend_from_slice(&kept_footers);
+    push_footer_header(&mut signed, footer_size)?;
+    signed.extend_from_slice(&(format as u32).to_le_bytes());
+    signed.extend_from_slice(&signature);
+
+    if reserved != 0 {
+        push_footer_header(&mut signed, reserved)?;
+        signed.extend_from_slice(&(TbfFooterV2CredentialsType::Reserved as u32).to_le_bytes());
+        signed.resize(total_size, 0);
+    }
+
+    Ok(signed)
+}
+
+/// Format and size of the data (after the format field) of the footer
+/// created by `key`.
+fn credentials_layout(
+    key: &PrivateKey,
+) -> Result<(TbfFooterV2CredentialsType, usize), TockloaderError> {
+    match key {
+        PrivateKey::Rsa(key) => match key.size() {
+            384 => Ok((TbfFooterV2CredentialsType::Rsa3072Key, 2 * 384)),
+            512 => Ok((TbfFooterV2CredentialsType::Rsa4096Key, 2 * 512)),
+            size => Err(CredentialsError::InvalidKey(format!(
+                "{}-bit RSA keys are not supported, use 3072 or 4096 bits",
+                size * 8
+            ))
+            .into()),
+        },
+        PrivateKey::EcdsaNistP256(_) => Ok((TbfFooterV2CredentialsType::EcdsaNistP256, 64)),
+    }
+}
+
+/// Data of the credentials footer for `integrity_region`: the public key
+/// followed by the signature for RSA, and the `r` and `s` values for ECDSA.
+fn sign(integrity_region: &[u8], key: &PrivateKey) -> Vec<u8> {
+    match key {
+        PrivateKey::Rsa(key) => {
+            let hash = Sha512::digest(integrity_region);
+            let mut data = key.n().to_bytes_be();
+            data.extend(
+                key.sign(Pkcs1v15Sign::new::<Sha512>(), &hash)
+                    .expect("key size was checked"),
+            );
+            data
+        }
+        PrivateKey::EcdsaNistP256(key) => {
+            let signature: Signature = key.sign(integrity_region);
+            signature.to_bytes().to_vec()
+        }
+    }
+}
+
+fn push_footer_header(tbf: &mut Vec<u8>, footer_size: usize) -> Result<(), TockloaderError> {
+    let length = u16::try_from(footer_size - TLV_HEADER_SIZE)
+        .map_err(|_| signing_error("the footer is too large"))?;
+
+    tbf.extend_from_slice(&(TbfHeaderTypes::TbfFooterCredentials as u16).to_le_bytes());
+    tbf.extend_from_slice(&length.to_le_bytes());
+    Ok(())
+}
+
+/// Whether the header contains a Program TLV, which holds the offset at which
+/// the footers start.
+fn has_program_header(header: &[u8]) -> bool {
+    // Skip the base header.
+    let mut offset = 16;
+    while let Some(tlv) = header.get(offset..offset + TLV_HEADER_SIZE) {
+        let tipe = u16::from_le_bytes([tlv[0], tlv[1]]);
+        let length = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+        if tipe == TbfHeaderTypes::TbfHeaderProgram as u16 {
+            return true;
+        }
+        // All TLV blocks are padded to 4 bytes.
+        offset += TLV_HEADER_SIZE + length.next_multiple_of(4);
+    }
+    false
+}
+
+fn signing_error(reason: &str) -> TockloaderError {
+    CredentialsError::Signing(reason.to_owned()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::SigningKey;
+    use tbf_parser::parse::parse_tbf_footer;
+
+    use super::*;
+    use crate::credentials::verify::{verify_tbf, VerificationStatus};
+
+    const SHA256_TBF: &[u8] = include_bytes!("../../../tbf-parser/tests/flashes/footerSHA256.dat");
+
+    fn ecdsa_key(seed: u8) -> PrivateKey {
+        PrivateKey::EcdsaNistP256(SigningKey::from_slice(&[seed; 32]).unwrap())
+    }
+
+    fn statuses(tbf: &[u8], key: &PrivateKey) -> Vec<VerificationStatus> {
+        verify_tbf(tbf, &[key.public_key()])
+            .unwrap()
+            .into_iter()
+            .map(|check| check.status)
+            .collect()
+    }
+
+    #[test]
+    fn uses_reserved_space() {
+        let key = ecdsa_key(7);
+        let signed = sign_tbf(SHA256_TBF, &key).unwrap();
+
+        assert_eq!(signed.len(), SHA256_TBF.len());
+        assert_eq!(signed[..5836], SHA256_TBF[..5836]);
+        assert_eq!(
+            statuses(&signed, &key),
+            vec![VerificationStatus::Verified, VerificationStatus::Verified]
+        );
+
+        // SHA256 footer, then the new ECDSA footer, then the reserved space.
+        let (_, size) = parse_tbf_footer(&signed[5836..]).unwrap();
+        let offset = 5836 + size as usize + 4;
+        let (footer, size) = parse_tbf_footer(&signed[offset..]).unwrap();
+        assert!(matches!(footer, TbfFooterV2Credentials::EcdsaNistP256(_)));
+        assert_eq!(size, 68);
+        let offset = offset + size as usize + 4;
+        let (footer, size) = parse_tbf_footer(&signed[offset..]).unwrap();
+        assert!(matches!(footer, TbfFooterV2Credentials::Reserved(_)));
+        assert_eq!(offset + size as usize + 4, signed.len());
+    }
+
+    #[test]
+    fn rejects_without_reserved_space() {
+        let key = ecdsa_key(7);
+        let mut signed = SHA256_TBF.to_vec();
+        let error = loop {
+            match sign_tbf(&signed, &key) {
+                Ok(tbf) => signed = tbf,
+                Err(error) => break error,
+            }
+        };
+
+        assert!(matches!(
+            error,
+            TockloaderError::Credentials(CredentialsError::Signing(_))
+        ));
+        // Every signature made before running out of space still matches.
+        assert_eq!(signed.len(), SHA256_TBF.len());
+        assert!(statuses(&signed, &key)
+            .iter()
+            .all(|status| *status == VerificationStatus::Verified));
+    }
+
+    #[test]
+    fn keeps_unknown_footers() {
+        // Put a footer of an unknown type between the SHA256 footer and the
+        // reserved space.
+        let unknown = [0xEF, 0xBE, 4, 0, 1, 2, 3, 4];
+        let mut tbf = SHA256_TBF.to_vec();
+        let reserved_start = 5836 + 40;
+        tbf[reserved_start..reserved_start + unknown.len()].copy_from_slice(&unknown);
+        let mut reserved = vec![];
+        push_footer_header(&mut reserved, tbf.len() - reserved_start - unknown.len()).unwrap();
+        reserved.extend_from_slice(&(TbfFooterV2CredentialsType::Reserved as u32).to_le_bytes());
+        let reserved_start = reserved_start + unknown.len();
+        tbf[reserved_start..reserved_start + reserved.len()].copy_from_slice(&reserved);
+
+        let key = ecdsa_key(7);
+        let signed = sign_tbf(&tbf, &key).unwrap();
+
+        assert_eq!(signed.len(), tbf.len());
+        assert_eq!(signed[..reserved_start], tbf[..reserved_start]);
+        assert_eq!(
+            statuses(&signed, &key),
+            vec![VerificationStatus::Verified, VerificationStatus::Verified]
+        );
+    }
+
+    #[test]
+    fn rejects_main_only_tbf() {
+        let simple = include_bytes!("../../../tbf-parser/tests/flashes/simple.dat");
+        let mut tbf = simple.to_vec();
+        tbf.resize(8192, 0);
+
+        assert!(sign_tbf(&tbf, &ecdsa_key(7)).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::fmt;
+
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::Signature;
+use rsa::traits::PublicKeyParts;
+use rsa::Pkcs1v15Sign;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use tbf_parser::types::{TbfFooterV2Credentials, TbfFooterV2CredentialsType};
+
+use crate::attributes::app_attributes::TbfFooter;
+use crate::credentials::keys::{rsa_from_modulus, PublicKey};
+use crate::credentials::parse_tbf;
+use crate::errors::TockloaderError;
+
+/// Result of checking a single credentials footer against an application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationStatus {
+    /// The hash or signature matches the application.
+    Verified,
+    /// The hash or signature does not match the application, which was
+    /// modified after the credentials were created.
+    Mismatch,
+    /// The application is signed, but none of the provided keys can check
+    /// the signature.
+    ///
+    /// ECDSA footers do not include the public key, so a signature that does
+    /// not verify with any of the provided keys is also reported as unknown.
+    UnknownKey,
+}
+
+impl fmt::Display for VerificationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationStatus::Verified => write!(f, "verified"),
+            VerificationStatus::Mismatch => write!(f, "mismatch"),
+            VerificationStatus::UnknownKey => write!(f, "unknown key"),
+        }
+    }
+}
+
+/// Status of one of the credentials footers of an application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CredentialsCheck {
+    /// Position of the footer among the credentials footers of the
+    /// application, including reserved ones. Footers of other types are not
+    /// counted.
+    pub index: usize,
+    pub format: TbfFooterV2CredentialsType,
+    pub status: VerificationStatus,
+}
+
+/// Status of all credentials footers of an application installed on a board.
+#[derive(Debug)]
+pub struct AppVerification {
+    /// Address of the application, as found in
+    /// [AppAttributes](crate::attributes::app_attributes::AppAttributes).
+    pub address: u64,
+    /// One entry per credentials footer, in footer order. Reserved footers are
+    /// skipped.
+    pub credentials: Vec<CredentialsCheck>,
+}
+
+/// Check a credentials footer against the integrity region of an application.
+///
+/// The integrity region spans from the start of the TBF header up to the end
+/// of the application binary (`binary_end_offset`). Hashes are recomputed over
+/// it. RSA signatures (PKCS#1 v1.5 over SHA-512) are checked with the
+/// key stored in the footer, if it is one of `keys`. ECDSA P-256 signatures
+/// (over SHA-256) are checked with every P-256 key in `keys`.
+///
+/// Returns `None` for reserved footers, which only hold padding.
+pub fn verify_credentials(
+    integrity_region: &[u8],
+    credentials: &TbfFooterV2Credentials,
+    keys: &[PublicKey],
+) -> Option<VerificationStatus> {
+    let status = match credentials {
+        TbfFooterV2Credentials::Reserved(_) => return None,
+        TbfFooterV2Credentials::SHA256(sha) => {
+            compare_hash(&Sha256::digest(integrity_region), sha.get_hash())
+        }
+        TbfFooterV2Credentials::SHA384(sha) => {
+            compare_hash(&Sha384::digest(integrity_region), sha.get_hash())
+        }
+        TbfFooterV2Credentials::SHA512(sha) => {
+            compare_hash(&Sha512::digest(integrity_region), sha.get_hash())
+        }
+        TbfFooterV2Credentials::Rsa3072Key(rsa) => verify_rsa(
+            integrity_region,
+            rsa.get_public_key(),
+            rsa.get_signature(),
+            keys,
+        ),
+        TbfFooterV2Credentials::Rsa4096Key(rsa) => verify_rsa(
+            integrity_region,
+            rsa.get_public_key(),
+            rsa.get_signature(),
+            keys,
+        ),
+        TbfFooterV2Credentials::EcdsaNistP256(ecdsa) => verify_ecdsa(
+            integrity_region,
+            ecdsa.get_signature_r(),
+            ecdsa.get_signature_s(),
+            keys,
+        ),
+    };
+
+    Some(status)
+}
+
+/// Check every credentials footer of an application. See
+/// [`verify_credentials`].
+pub fn verify_footers(
+    integrity_region: &[u8],
+    footers: &[TbfFooter],
+    keys: &[PublicKey],
+) -> Vec<CredentialsCheck> {
+    footers
+        .iter()
+        .enumerate()
+        .filter_map(|(index, footer)| {
+            verify_credentials(integrity_region, &footer.credentials, keys).map(|status| {
+                CredentialsCheck {
+                    index,
+                    format: credentials_format(&footer.credentials),
+                    status,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Check every credentials footer of a TBF binary, such as one stored in a
+/// TAB.
+pub fn verify_tbf(
+    tbf: &[u8],
+    keys: &[PublicKey],
+) -> Result<Vec<CredentialsCheck>, TockloaderError> {
+    let tbf = parse_tbf(tbf)?;
+    let footers: Vec<TbfFooter> = tbf.footers.into_iter().map(|(_, footer)| footer).collect();
+
+    Ok(verify_footers(tbf.integrity_region, &footers, keys))
+}
+
+pub(crate) fn credentials_format(
+    credentials: &TbfFooterV2Credentials,
+) -> TbfFooterV2CredentialsType {
+    match credentials {
+        TbfFooterV2Credentials::Reserved(_) => TbfFooterV2CredentialsType::Reserved,
+        TbfFooterV2Credentials::Rsa3072Key(_) => TbfFooterV2CredentialsType::Rsa3072Key,
+        TbfFooterV2Credentials::Rsa4096Key(_) => TbfFooterV2CredentialsType::Rsa4096Key,
+        TbfFooterV2Credentials::SHA256(_) => TbfFooterV2CredentialsType::SHA256,
+        TbfFooterV2Credentials::SHA384(_) => TbfFooterV2CredentialsType::SHA384,
+        TbfFooterV2Credentials::SHA512(_) => TbfFooterV2CredentialsType::SHA512,
+        TbfFooterV2Credentials::EcdsaNistP256(_) => TbfFooterV2CredentialsType::EcdsaNistP256,
+    }
+}
+
+fn compare_hash(computed: &[u8], expected: &[u8]) -> VerificationStatus {
+    if computed == expected {
+        VerificationStatus::Verified
+    } else {
+        VerificationStatus::Mismatch
+    }
+}
+
+fn verify_rsa(
+    integrity_region: &[u8],
+    modulus: &[u8],
+    signature: &[u8],
+    keys: &[PublicKey],
+) -> VerificationStatus {
+    let Ok(footer_key) = rsa_from_modulus(modulus) else {
+        return VerificationStatus::UnknownKey;
+    };
+
+    let known = keys.iter().any(|key| match key {
+        PublicKey::Rsa(key) => key.n() == footer_key.n() && key.e() == footer_key.e(),
+        PublicKey::EcdsaNistP256(_) => false,
+    });
+    if !known {
+        return VerificationStatus::UnknownKey;
+    }
+
+    let hash = Sha512::digest(integrity_region);
+    match footer_key.verify(Pkcs1v15Sign::new::<Sha512>(), &hash, signature) {
+        Ok(()) => VerificationStatus::Verified,
+        Err(_) => VerificationStatus::Mismatch,
+    }
+}
+
+fn verify_ecdsa(
+    integrity_region: &[u8],
+    r: &[u8; 32],
+    s: &[u8; 32],
+    keys: &[PublicKey],
+) -> VerificationStatus {
+    let Ok(signature) = Signature::from_scalars(*r, *s) else {
+        return VerificationStatus::Mismatch;
+    };
+
+    let verified = keys.iter().any(|key| match key {
+        PublicKey::EcdsaNistP256(key) => key.verify(integrity_region, &signature).is_ok(),
+        PublicKey::Rsa(_) => false,
+    });
+
+    if verified {
+        VerificationStatus::Verified
+    } else {
+        VerificationStatus::UnknownKey
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+
+    use super::*;
+
+    const SHA256_TBF: &[u8] = include_bytes!("../../../tbf-parser/tests/flashes/footerSHA256.dat");
+    const RSA_TBF: &[u8] = include_bytes!("../../../tbf-parser/tests/flashes/footerRSA4096.dat");
+    const RSA_KEY: &[u8] = include_bytes!("../../../tbf-parser/tests/flashes/RSA4096.key");
+
+    fn statuses(tbf: &[u8], keys: &[PublicKey]) -> Vec<VerificationStatus> {
+        verify_tbf(tbf, keys)
+            .unwrap()
+            .into_iter()
+            .map(|check| check.status)
+            .collect()
+    }
+
+    #[test]
+    fn sha256() {
+        assert_eq!(
+            verify_tbf(SHA256_TBF, &[]).unwrap(),
+            vec![CredentialsCheck {
+                index: 0,
+                format: TbfFooterV2CredentialsType::SHA256,
+                status: VerificationStatus::Verified,
+            }]
+        );
+
+        let mut tampered = SHA256_TBF.to_vec();
+        tampered[100] ^= 0xFF;
+        assert_eq!(statuses(&tampered, &[]), vec![VerificationStatus::Mismatch]);
+    }
+
+    #[test]
+    fn rsa4096() {
+        let key = PublicKey::from_bytes(RSA_KEY).unwrap();
+
+        assert_eq!(
+            statuses(RSA_TBF, std::slice::from_ref(&key)),
+            vec![VerificationStatus::Verified]
+        );
+        assert_eq!(statuses(RSA_TBF, &[]), vec![VerificationStatus::UnknownKey]);
+
+        let mut tampered = RSA_TBF.to_vec();
+        tampered[100] ^= 0xFF;
+        assert_eq!(
+            statuses(&tampered, &[key]),
+            vec![VerificationStatus::Mismatch]
+        );
+    }
+
+    #[test]
+    fn ecdsa_p256() {
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let other_key = SigningKey::from_slice(&[9; 32]).unwrap();
+        let key = PublicKey::EcdsaNistP256(*signing_key.verifying_key());
+        let other = PublicKey::EcdsaNistP256(*other_key.verifying_key());
+
+        let region = b"integrity region";
+        let signature: Signature = signing_key.sign(region);
+        let (r, s) = signature.split_bytes();
+
+        let mut footer = (TbfFooterV2CredentialsType::EcdsaNistP256 as u32)
+            .to_le_bytes()
+            .to_vec();
+        footer.extend_from_slice(&r);
+        footer.extend_from_slice(&s);
+        let credentials = TbfFooterV2Credentials::try_from(&footer[..]).unwrap();
+
+        assert_eq!(
+            verify_credentials(region, &credentials, &[other.clone(), key]),
+            Some(VerificationStatus::Verified)
+        );
+        assert_eq!(
+            verify_credentials(region, &credentials, &[other]),
+            Some(VerificationStatus::UnknownKey)
+        );
+    }
+
+    #[test]
+    fn padding_is_skipped() {
+        let mut padding = (TbfFooterV2CredentialsType::Reserved as u32)
+            .to_le_bytes()
+            .to_vec();
+        padding.extend_from_slice(&[0; 16]);
+        let credentials = TbfFooterV2Credentials::try_from(&padding[..]).unwrap();
+
+        assert_eq!(verify_credentials(&[], &credentials, &[]), None);
+    }
+}
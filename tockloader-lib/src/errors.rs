@@ -30,6 +30,11 @@ pub enum TockloaderError {
     #[error("Tock OS error: {0}")]
     Tock(#[from] TockError),
 
+    /// Represents an error that can occur while loading the keys used to
+    /// check or create application credentials.
+    #[error("Credentials error: {0}")]
+    Credentials(#[from] CredentialsError),
+
     /// Represents an error that occurs from internal violations of assumptions,
     /// or inconsistent state. It usually represents something that the user of
     /// this library did wrong.
@@ -71,14 +76,20 @@ pub enum TabError {
     #[error("Failed to parse metadata: {0}")]
     InvalidMetadata(toml::de::Error),
 
+    #[error("Failed to serialize metadata: {0}")]
+    MetadataSerialization(toml::ser::Error),
+
     #[error("No metadata.toml found inside the tab file.")]
     MissingMetadata,
 
-    #[error("App data could not be parsed from tab file: {0:?}")]
-    Parsing(tbf_parser::types::TbfParseError),
+    #[error("App data could not be parsed from tab file: {0}")]
+    Parsing(#[source] tbf_parser::types::TbfParseError),
 
     #[error("No binary data found for {0} architecture")]
     MissingBinary(String),
+
+    #[error("{0} does not start with a valid TBF header")]
+    InvalidTbf(String),
 }
 
 /// Represents errors that can occur while parsing Tock OS data or otherwise
@@ -88,17 +99,89 @@ pub enum TockError {
     #[error("Bootloader returned an invalid header: {0} {1}")]
     BootloaderBadHeader(u8, u8),
 
+    #[error("Bootloader rejected the address of the command")]
+    BootloaderBadAddress,
+
+    #[error("Bootloader rejected the arguments of the command")]
+    BootloaderBadArguments,
+
+    #[error("Bootloader failed with an internal error")]
+    BootloaderInternalError,
+
+    #[error("Bootloader command buffer overflowed")]
+    BootloaderOverflow,
+
+    #[error("Bootloader does not know the command")]
+    BootloaderUnknownCommand,
+
+    #[error("Bootloader received data with a bad CRC")]
+    BootloaderCrcMismatch,
+
+    #[error("Bootloader sent an escape character followed by {0:#x}")]
+    BootloaderBadEscape(u8),
+
     #[error("Bootloader command did not finish in time")]
     BootloaderTimeout,
 
-    #[error("Application data could not be parsed due to malformed header: {0:?}")]
-    InvalidAppTbfHeader(tbf_parser::types::TbfParseError),
+    #[error("Application data could not be parsed due to malformed header: {0}")]
+    InvalidAppTbfHeader(#[source] tbf_parser::types::TbfParseError),
 
     #[error("Failed to parse attribute: {0}")]
     AttributeParsing(#[from] AttributeParseError),
 
     #[error("Attribute does not exist: {0}")]
     MissingAttribute(String),
+
+    #[error("Invalid attribute: {0}")]
+    InvalidAttribute(String),
+
+    #[error("All attribute slots are in use")]
+    AttributeSlotsFull,
+
+    #[error("Refusing to downgrade the bootloader from {current} to {new}")]
+    BootloaderDowngrade { current: String, new: String },
+
+    #[error("Bootloader version is {found:?} after flashing, expected {expected}")]
+    BootloaderVersionMismatch {
+        expected: String,
+        found: Option<String>,
+    },
+
+    #[error("The bootloader cannot update itself over serial, use a debug probe")]
+    BootloaderSelfUpdateUnsupported,
+
+    #[error("Address {0:#x} does not fit in the 32 bits of the bootloader protocol")]
+    BootloaderAddressOutOfRange(u64),
+
+    #[error("Invalid bootloader info: {0}")]
+    InvalidBootloaderInfo(String),
+
+    #[error("External flash failed to answer in time")]
+    ExternalFlashTimeout,
+
+    #[error("External flash failed to erase a page")]
+    ExternalFlashEraseFailed,
+
+    #[error("External flash CRC mismatch at {address:#x}: expected {expected:#010x}, found {found:#010x}")]
+    ExternalFlashCrcMismatch {
+        address: u64,
+        expected: u32,
+        found: u32,
+    },
+
+    #[error("Invalid kernel image: {0}")]
+    InvalidKernelImage(String),
+
+    #[error("Kernel ends at {end:#x}, past the start of applications at {app_start:#x}")]
+    KernelTooLarge { end: u64, app_start: u64 },
+
+    #[error("Kernel is linked for {linked:#x}, but the board expects it at {expected:#x}")]
+    KernelAddressMismatch { linked: u64, expected: u64 },
+
+    #[error(
+        "Kernel address {address:#x} is below the kernel address of the board, {board_address:#x}"
+    )]
+    KernelBelowBoardAddress { address: u64, board_address: u64 },
 }
 
 /// Represents errors that can occur while parsing attributes.
@@ -111,6 +194,20 @@ pub enum AttributeParseError {
     InvalidString(#[from] std::string::FromUtf8Error),
 }
 
+/// Represents errors that can occur while loading the keys used to check or
+/// create application credentials.
+#[derive(Debug, Error)]
+pub enum CredentialsError {
+    #[error("Failed to read key file: {0}")]
+    IO(io::Error),
+
+    #[error("Unsupported or malformed key: {0}")]
+    InvalidKey(String),
+
+    #[error("Failed to sign app: {0}")]
+    Signing(String),
+}
+
 /// Represents internal violations of assumptions, or inconsistent state. It
 /// usually represents something that the user of this library did wrong.
 #[derive(Debug, Error)]
@@ -123,6 +220,15 @@ pub enum InternalError {
 
     #[error("Missing or invalid board setting: {0}")]
     MisconfiguredBoardSettings(String),
+
+    #[error("Operation is only available over a serial connection to the bootloader.")]
+    BootloaderRequired,
+
+    #[error("{value:#x} is not aligned to the {alignment}-byte pages of the external flash")]
+    UnalignedExternalFlash { value: u64, alignment: u64 },
+
+    #[error("Read of {length} bytes at {address:#x} returned less data")]
+    ShortRead { address: u64, length: usize },
 }
 
 impl From<tokio_serial::Error> for TockloaderError {
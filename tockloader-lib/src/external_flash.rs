@@ -0,0 +1,80 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::ops::Range;
+
+use crate::errors::InternalError;
+
+/// Size of an external flash page, the unit written by `XWPage` and erased by
+/// `XEPage`.
+pub const EXTERNAL_PAGE_SIZE: u64 = 512;
+
+/// Size of an external flash block, the unit erased by `XEBlock`.
+pub const EXTERNAL_BLOCK_SIZE: u64 = 4096;
+
+/// A single erase command of the external flash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EraseUnit {
+    Page(u64),
+    Block(u64),
+}
+
+/// Check that `value`, an address or a length, is a whole number of external
+/// flash pages.
+pub(crate) fn check_page_aligned(value: u64) -> Result<(), InternalError> {
+    if !value.is_multiple_of(EXTERNAL_PAGE_SIZE) {
+        return Err(InternalError::UnalignedExternalFlash {
+            value,
+            alignment: EXTERNAL_PAGE_SIZE,
+        });
+    }
+    Ok(())
+}
+
+/// Split the page-aligned `range` into erase commands: a block for every
+/// block it fully covers, and pages for the rest.
+pub(crate) fn erase_units(range: Range<u64>) -> Vec<EraseUnit> {
+    let mut units = vec![];
+    let mut address = range.start;
+    while address < range.end {
+        if address.is_multiple_of(EXTERNAL_BLOCK_SIZE) && address + EXTERNAL_BLOCK_SIZE <= range.end
+        {
+            units.push(EraseUnit::Block(address));
+            address += EXTERNAL_BLOCK_SIZE;
+        } else {
+            units.push(EraseUnit::Page(address));
+            address += EXTERNAL_PAGE_SIZE;
+        }
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alignment() {
+        assert!(check_page_aligned(0).is_ok());
+        assert!(check_page_aligned(0x1200).is_ok());
+        assert!(check_page_aligned(0x1201).is_err());
+        assert!(check_page_aligned(100).is_err());
+    }
+
+    #[test]
+    fn erase_commands() {
+        use EraseUnit::{Block, Page};
+
+        // Two pages, a whole block, then one page.
+        assert_eq!(
+            erase_units(0x0C00..0x2200),
+            vec![Page(0x0C00), Page(0x0E00), Block(0x1000), Page(0x2000)]
+        );
+        assert_eq!(
+            erase_units(0x2000..0x4000),
+            vec![Block(0x2000), Block(0x3000)]
+        );
+        assert_eq!(erase_units(0x1000..0x1000), vec![]);
+    }
+}
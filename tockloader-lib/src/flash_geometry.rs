@@ -0,0 +1,262 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::ops::Range;
+
+use probe_rs::Target;
+
+/// Page size used when nothing else is known about the flash. This is also
+/// the size of a `WritePage` command of the serial bootloader.
+pub const DEFAULT_PAGE_SIZE: u64 = 512;
+
+/// A part of the flash made of equally sized pages (or sectors), which are
+/// the smallest units that can be erased.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashRegion {
+    pub range: Range<u64>,
+    pub page_size: u64,
+}
+
+/// Erase geometry of the flash of a board. Non-uniform flash, such as the
+/// 16/64/128 KiB sectors of STM32F4 chips, is described by one
+/// [FlashRegion] per sector size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashGeometry {
+    /// Regions, sorted by address and not overlapping.
+    regions: Vec<FlashRegion>,
+    /// Page size of the addresses that are not part of any region.
+    default_page_size: u64,
+}
+
+impl FlashGeometry {
+    /// Flash made only of pages of `page_size` bytes.
+    pub fn uniform(page_size: u64) -> Self {
+        FlashGeometry {
+            regions: vec![],
+            default_page_size: page_size,
+        }
+    }
+
+    /// Flash made of the given regions. Addresses outside of them use pages of
+    /// `default_page_size` bytes.
+    pub fn new(mut regions: Vec<FlashRegion>, default_page_size: u64) -> Self {
+        regions.sort_by_key(|region| region.range.start);
+        FlashGeometry {
+            regions,
+            default_page_size,
+        }
+    }
+
+    /// Build the geometry from the sector descriptions of the flash
+    /// algorithms of a probe-rs target.
+    pub(crate) fn from_target(target: &Target) -> Self {
+        let mut regions: Vec<FlashRegion> = vec![];
+        for algorithm in &target.flash_algorithms {
+            let properties = &algorithm.flash_properties;
+            let flash = &properties.address_range;
+
+            // Each sector description starts a group of sectors that runs
+            // until the next description or the end of the flash.
+            for (i, sector) in properties.sectors.iter().enumerate() {
+                let start = flash.start + sector.address;
+                let end = properties
+                    .sectors
+                    .get(i + 1)
+                    .map_or(flash.end, |next| flash.start + next.address);
+                if sector.size == 0
+                    || start >= end
+                    || regions
+                        .iter()
+                        .any(|region| region.range.start < end && start < region.range.end)
+                {
+                    continue;
+                }
+                regions.push(FlashRegion {
+                    range: start..end,
+                    page_size: sector.size,
+                });
+            }
+        }
+        FlashGeometry::new(regions, DEFAULT_PAGE_SIZE)
+    }
+
+    /// The page that contains `address`.
+    pub fn page_at(&self, address: u64) -> Range<u64> {
+        let (base, page_size) = self
+            .regions
+            .iter()
+            .find(|region| region.range.contains(&address))
+            .map_or((0, self.default_page_size), |region| {
+                (region.range.start, region.page_size)
+            });
+        let start = base + (address - base) / page_size * page_size;
+        start..start + page_size
+    }
+
+    /// Round `address` up to the start of a page.
+    pub fn align_up(&self, address: u64) -> u64 {
+        let page = self.page_at(address);
+        if page.start == address {
+            address
+        } else {
+            page.end
+        }
+    }
+
+    /// Split `range` into the pages it covers. The first and last pages are
+    /// cut to stay inside `range`.
+    pub fn pages(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        let mut pages = vec![];
+        let mut address = range.start;
+        while address < range.end {
+            let page = self.page_at(address);
+            let end = page.end.min(range.end);
+            pages.push(address..end);
+            address = end;
+        }
+        pages
+    }
+
+    /// Choose the pages of `binary`, to be written at `address`, that need to
+    /// be written:
+    ///
+    /// - pages that hold at least one non-zero byte;
+    /// - the blank page right after each of those, as there might be a usable
+    ///   0 on the next page;
+    /// - every page if all of them are blank, so that old data is cleared and
+    ///   there are no partially written apps.
+    ///
+    /// # Returns
+    /// The address ranges of the selected pages, in order.
+    pub(crate) fn pages_to_write(&self, address: u64, binary: &[u8]) -> Vec<Range<u64>> {
+        let pages = self.pages(address..address + binary.len() as u64);
+        let is_valid: Vec<bool> = pages
+            .iter()
+            .map(|page| {
+                binary[(page.start - address) as usize..(page.end - address) as usize]
+                    .iter()
+                    .any(|&b| b != 0)
+            })
+            .collect();
+
+        if !is_valid.contains(&true) {
+            return pages;
+        }
+
+        pages
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| is_valid[i] || (i > 0 && is_valid[i - 1]))
+            .map(|(_, page)| page)
+            .collect()
+    }
+}
+
+impl Default for FlashGeometry {
+    fn default() -> Self {
+        FlashGeometry::uniform(DEFAULT_PAGE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bank 1 of an STM32F429: 4 sectors of 16 KiB, 1 of 64 KiB and 7 of
+    /// 128 KiB.
+    fn stm32f4() -> FlashGeometry {
+        FlashGeometry::new(
+            vec![
+                FlashRegion {
+                    range: 0x0802_0000..0x0810_0000,
+                    page_size: 0x20000,
+                },
+                FlashRegion {
+                    range: 0x0800_0000..0x0801_0000,
+                    page_size: 0x4000,
+                },
+                FlashRegion {
+                    range: 0x0801_0000..0x0802_0000,
+                    page_size: 0x10000,
+                },
+            ],
+            DEFAULT_PAGE_SIZE,
+        )
+    }
+
+    #[test]
+    fn uniform() {
+        let geometry = FlashGeometry::uniform(4096);
+        assert_eq!(geometry.page_at(0x40010), 0x40000..0x41000);
+        assert_eq!(geometry.align_up(0x40000), 0x40000);
+        assert_eq!(geometry.align_up(0x40001), 0x41000);
+        assert_eq!(
+            geometry.pages(0x40000..0x42800),
+            vec![0x40000..0x41000, 0x41000..0x42000, 0x42000..0x42800]
+        );
+    }
+
+    #[test]
+    fn non_uniform() {
+        let geometry = stm32f4();
+        assert_eq!(geometry.page_at(0x0800_4100), 0x0800_4000..0x0800_8000);
+        assert_eq!(geometry.page_at(0x0801_2000), 0x0801_0000..0x0802_0000);
+        assert_eq!(geometry.page_at(0x0804_0000), 0x0804_0000..0x0806_0000);
+        assert_eq!(geometry.align_up(0x0800_C001), 0x0801_0000);
+        assert_eq!(
+            geometry.pages(0x0800_C000..0x0803_0000),
+            vec![
+                0x0800_C000..0x0801_0000,
+                0x0801_0000..0x0802_0000,
+                0x0802_0000..0x0803_0000,
+            ]
+        );
+
+        // Outside of the described flash.
+        assert_eq!(geometry.page_at(0x0900_0010), 0x0900_0000..0x0900_0200);
+    }
+
+    #[test]
+    fn large_binary() {
+        // 384 KiB, or 768 pages of 512 bytes.
+        let mut binary = vec![0xAA; 384 * 1024];
+        // Blank out pages 300 to 599.
+        binary[300 * 512..600 * 512].fill(0);
+
+        let address = 0x40000;
+        let pages = FlashGeometry::default().pages_to_write(address, &binary);
+        let page = |i: u64| address + i * 512..address + (i + 1) * 512;
+
+        // Every page with data, and the blank page right after page 299.
+        assert_eq!(pages.len(), 300 + 1 + 168);
+        assert_eq!(pages[0], page(0));
+        assert_eq!(pages[299], page(299));
+        assert_eq!(pages[300], page(300));
+        assert_eq!(pages[301], page(600));
+        assert_eq!(pages.last(), Some(&page(767)));
+    }
+
+    #[test]
+    fn trailing_blank_page() {
+        let geometry = FlashGeometry::uniform(4096);
+        let mut binary = vec![0; 5 * 4096];
+        binary[10] = 1;
+        binary[3 * 4096 + 4095] = 1;
+
+        assert_eq!(
+            geometry.pages_to_write(0, &binary),
+            vec![0..0x1000, 0x1000..0x2000, 0x3000..0x4000, 0x4000..0x5000]
+        );
+
+        // No page past the end of the binary.
+        binary[4 * 4096] = 1;
+        assert_eq!(
+            geometry.pages_to_write(0, &binary).last(),
+            Some(&(0x4000..0x5000))
+        );
+
+        // All blank: everything is written.
+        assert_eq!(geometry.pages_to_write(0, &[0; 3 * 4096]).len(), 3);
+    }
+}
@@ -0,0 +1,304 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use object::elf::PT_LOAD;
+use object::read::elf::{ElfFile32, ProgramHeader};
+use object::Endianness;
+
+use crate::attributes::system_attributes::SystemAttributes;
+use crate::board_settings::BoardSettings;
+use crate::errors::{InternalError, TockError, TockloaderError};
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+
+/// Largest distance between the start of a kernel and the end of its last
+/// segment. No board has that much internal flash, so segments further away
+/// are loaded somewhere else, such as in RAM.
+const MAX_KERNEL_SPAN: u64 = 16 * 1024 * 1024;
+
+/// A Tock kernel, ready to be written to flash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelImage {
+    /// Flash address the kernel was linked for. Only known for ELF files.
+    pub address: Option<u64>,
+    pub data: Vec<u8>,
+}
+
+impl KernelImage {
+    /// Load a kernel from the contents of either an ELF file or a raw binary.
+    pub fn new(data: Vec<u8>) -> Result<Self, TockloaderError> {
+        if data.starts_with(ELF_MAGIC) {
+            Self::from_elf(&data)
+        } else {
+            Ok(Self::from_binary(data))
+        }
+    }
+
+    /// Use a raw binary as the kernel. Its address comes from the board.
+    pub fn from_binary(data: Vec<u8>) -> Self {
+        KernelImage {
+            address: None,
+            data,
+        }
+    }
+
+    /// Extract the loadable segments of a 32-bit ELF file, placed at their
+    /// physical (load) address. Gaps between segments are filled with 0xFF.
+    /// Segments more than [MAX_KERNEL_SPAN] bytes past the lowest one are
+    /// skipped.
+    pub fn from_elf(data: &[u8]) -> Result<Self, TockloaderError> {
+        let elf = ElfFile32::<Endianness>::parse(data)
+            .map_err(|e| TockError::InvalidKernelImage(e.to_string()))?;
+        let endian = elf.endian();
+
+        let mut segments = Vec::new();
+        for header in elf.raw_segments() {
+            if header.p_type(endian) != PT_LOAD || header.p_filesz(endian) == 0 {
+                continue;
+            }
+            let segment = header.data(endian, data).map_err(|()| {
+                TockError::InvalidKernelImage("segment runs past the end of the file".to_owned())
+            })?;
+            segments.push((u64::from(header.p_paddr(endian)), segment));
+        }
+
+        segments.sort_by_key(|(address, _)| *address);
+        let start = segments
+            .first()
+            .map(|(address, _)| *address)
+            .ok_or_else(|| TockError::InvalidKernelImage("no loadable segments".to_owned()))?;
+        // Keep the image from spanning up to the RAM, which would take
+        // hundreds of megabytes.
+        segments.retain(|(address, segment)| {
+            let keep = address + segment.len() as u64 - start <= MAX_KERNEL_SPAN;
+            if !keep {
+                log::warn!(
+                    "Skipping the segment at {address:#x}, too far from the kernel at {start:#x}"
+                );
+            }
+            keep
+        });
+        let end = segments
+            .iter()
+            .map(|(address, segment)| address + segment.len() as u64)
+            .max()
+            .expect("the first segment is always kept");
+
+        let mut image = vec![0xFF; (end - start) as usize];
+        for (address, segment) in segments {
+            let offset = (address - start) as usize;
+            image[offset..offset + segment.len()].copy_from_slice(segment);
+        }
+
+        Ok(KernelImage {
+            address: Some(start),
+            data: image,
+        })
+    }
+
+    /// Choose where the kernel is written and check that it lies between the
+    /// kernel address of the board and the start of applications.
+    ///
+    /// The kernel address of the board is the `kernel_bin_start` kernel
+    /// attribute or the [BoardSettings::kernel_address]. An ELF file linked
+    /// for another address is refused unless `force` is set, in which case
+    /// it is written where it was linked. The start of applications is the
+    /// `appaddr` attribute or the [BoardSettings::start_address].
+    pub(crate) fn placement(
+        &self,
+        system: Option<&SystemAttributes>,
+        settings: &BoardSettings,
+        force: bool,
+    ) -> Result<u64, TockloaderError> {
+        let board_address = system
+            .and_then(|system| system.kernel_bin_start)
+            .map(u64::from)
+            .or(settings.kernel_address);
+        let address = match (self.address, board_address) {
+            (Some(address), Some(board_address)) if address != board_address => {
+                if !force {
+                    return Err(TockError::KernelAddressMismatch {
+                        linked: address,
+                        expected: board_address,
+                    }
+                    .into());
+                }
+                log::warn!(
+                    "Kernel is linked for {address:#x}, but the board expects it at {board_address:#x}"
+                );
+                address
+            }
+            (Some(address), _) | (None, Some(address)) => address,
+            (None, None) => {
+                return Err(
+                    InternalError::MisconfiguredBoardSettings("kernel address".to_owned()).into(),
+                )
+            }
+        };
+
+        // Whatever comes before the kernel, such as a bootloader, is kept.
+        if let Some(board_address) = board_address.filter(|&board_address| address < board_address)
+        {
+            return Err(TockError::KernelBelowBoardAddress {
+                address,
+                board_address,
+            }
+            .into());
+        }
+
+        let app_start = system
+            .and_then(|system| system.appaddr)
+            .unwrap_or(settings.start_address);
+        let end = address + self.data.len() as u64;
+        if end > app_start {
+            return Err(TockError::KernelTooLarge { end, app_start }.into());
+        }
+
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a little-endian ELF32 file with one program header per segment:
+    /// (type, virtual address, physical address, data).
+    fn elf32(segments: &[(u32, u32, u32, &[u8])]) -> Vec<u8> {
+        let phoff = 52;
+        let mut offset = phoff + 32 * segments.len() as u32;
+
+        let mut elf = vec![0x7F, b'E', b'L', b'F', 1, 1, 1, 0];
+        elf.resize(16, 0);
+        elf.extend_from_slice(&2u16.to_le_bytes()); // e_type: executable
+        elf.extend_from_slice(&40u16.to_le_bytes()); // e_machine: ARM
+        elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+        elf.extend_from_slice(&0u32.to_le_bytes()); // e_entry
+        elf.extend_from_slice(&phoff.to_le_bytes()); // e_phoff
+        elf.extend_from_slice(&0u32.to_le_bytes()); // e_shoff
+        elf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+        elf.extend_from_slice(&52u16.to_le_bytes()); // e_ehsize
+        elf.extend_from_slice(&32u16.to_le_bytes()); // e_phentsize
+        elf.extend_from_slice(&(segments.len() as u16).to_le_bytes()); // e_phnum
+        elf.extend_from_slice(&40u16.to_le_bytes()); // e_shentsize
+        elf.extend_from_slice(&0u16.to_le_bytes()); // e_shnum
+        elf.extend_from_slice(&0u16.to_le_bytes()); // e_shstrndx
+
+        for (tipe, vaddr, paddr, data) in segments {
+            let size = data.len() as u32;
+            for field in [*tipe, offset, *vaddr, *paddr, size, size, 0, 4] {
+                elf.extend_from_slice(&field.to_le_bytes());
+            }
+            offset += size;
+        }
+        for (_, _, _, data) in segments {
+            elf.extend_from_slice(data);
+        }
+        elf
+    }
+
+    #[test]
+    fn elf_segments() {
+        let elf = elf32(&[
+            // .text
+            (PT_LOAD, 0x8000, 0x8000, &[1, 2, 3, 4]),
+            // A note that is not loaded.
+            (4, 0, 0, &[9, 9]),
+            // .data, which runs from RAM but is loaded after .text.
+            (PT_LOAD, 0x2000_0000, 0x8008, &[5, 6]),
+        ]);
+
+        let kernel = KernelImage::new(elf).unwrap();
+        assert_eq!(kernel.address, Some(0x8000));
+        assert_eq!(kernel.data, vec![1, 2, 3, 4, 0xFF, 0xFF, 0xFF, 0xFF, 5, 6]);
+    }
+
+    #[test]
+    fn ram_segment() {
+        let elf = elf32(&[
+            (PT_LOAD, 0x0, 0x0, &[1, 2, 3, 4]),
+            // Left at its RAM address by the linker script.
+            (PT_LOAD, 0x2000_0000, 0x2000_0000, &[5, 6]),
+            (PT_LOAD, 0xFFFF_F000, 0xFFFF_F000, &[7]),
+        ]);
+
+        let kernel = KernelImage::new(elf).unwrap();
+        assert_eq!(kernel.address, Some(0x0));
+        assert_eq!(kernel.data, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn invalid_elf() {
+        assert!(KernelImage::new(elf32(&[(4, 0, 0, &[1])])).is_err());
+        assert!(KernelImage::new(b"\x7fELF\x02".to_vec()).is_err());
+        assert_eq!(KernelImage::new(vec![1, 2]).unwrap().address, None);
+    }
+
+    #[test]
+    fn placement() {
+        let settings = BoardSettings {
+            arch: None,
+            start_address: 0x40000,
+            kernel_address: Some(0x8000),
+            flash_geometry: None,
+        };
+        let kernel = KernelImage::from_binary(vec![0; 0x38000]);
+        assert_eq!(kernel.placement(None, &settings, false).unwrap(), 0x8000);
+
+        let mut system = SystemAttributes::new();
+        system.kernel_bin_start = Some(0x10000);
+        system.appaddr = Some(0x48000);
+        assert_eq!(
+            kernel.placement(Some(&system), &settings, false).unwrap(),
+            0x10000
+        );
+
+        system.appaddr = Some(0x40000);
+        assert!(matches!(
+            kernel.placement(Some(&system), &settings, false),
+            Err(TockloaderError::Tock(TockError::KernelTooLarge {
+                end: 0x48000,
+                app_start: 0x40000
+            }))
+        ));
+
+        let settings = BoardSettings::default();
+        assert!(kernel.placement(None, &settings, false).is_err());
+    }
+
+    #[test]
+    fn placement_mismatch() {
+        let settings = BoardSettings {
+            arch: None,
+            start_address: 0x40000,
+            kernel_address: Some(0x8000),
+            flash_geometry: None,
+        };
+        let kernel = KernelImage {
+            address: Some(0x10000),
+            data: vec![0; 0x1000],
+        };
+        assert!(matches!(
+            kernel.placement(None, &settings, false),
+            Err(TockloaderError::Tock(TockError::KernelAddressMismatch {
+                linked: 0x10000,
+                expected: 0x8000
+            }))
+        ));
+        assert_eq!(kernel.placement(None, &settings, true).unwrap(), 0x10000);
+
+        // Linked over the bootloader.
+        let kernel = KernelImage {
+            address: Some(0x0),
+            data: vec![0; 0x1000],
+        };
+        assert!(matches!(
+            kernel.placement(None, &settings, true),
+            Err(TockloaderError::Tock(TockError::KernelBelowBoardAddress {
+                address: 0x0,
+                board_address: 0x8000
+            }))
+        ));
+    }
+}
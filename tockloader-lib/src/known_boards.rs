@@ -1,5 +1,8 @@
+use probe_rs::probe::WireProtocol;
+
 use crate::board_settings::BoardSettings;
 use crate::connection::{ProbeTargetInfo, SerialTargetInfo};
+use crate::flash_geometry::{FlashGeometry, FlashRegion, DEFAULT_PAGE_SIZE};
 
 pub trait KnownBoard {
     fn serial_target_info(&self) -> SerialTargetInfo;
@@ -18,6 +21,10 @@ impl KnownBoard for NucleoF4 {
         ProbeTargetInfo {
             chip: "STM32F429ZIT".to_string(),
             core: 0,
+            protocol: Some(WireProtocol::Swd),
+            speed_khz: None,
+            connect_under_reset: false,
+            allow_erase_all: false,
         }
     }
 
@@ -25,6 +32,30 @@ impl KnownBoard for NucleoF4 {
         BoardSettings {
             arch: Some("cortex-m4".to_string()),
             start_address: 0x08040000,
+            kernel_address: Some(0x08000000),
+            // 4 sectors of 16 KiB, 1 of 64 KiB and 7 of 128 KiB in each bank.
+            flash_geometry: Some(FlashGeometry::new(
+                [0x08000000, 0x08100000]
+                    .into_iter()
+                    .flat_map(|bank| {
+                        [
+                            FlashRegion {
+                                range: bank..bank + 0x10000,
+                                page_size: 0x4000,
+                            },
+                            FlashRegion {
+                                range: bank + 0x10000..bank + 0x20000,
+                                page_size: 0x10000,
+                            },
+                            FlashRegion {
+                                range: bank + 0x20000..bank + 0x100000,
+                                page_size: 0x20000,
+                            },
+                        ]
+                    })
+                    .collect(),
+                DEFAULT_PAGE_SIZE,
+            )),
         }
     }
 }
@@ -40,6 +71,10 @@ impl KnownBoard for MicrobitV2 {
         ProbeTargetInfo {
             chip: "nRF52833".to_string(),
             core: 0,
+            protocol: Some(WireProtocol::Swd),
+            speed_khz: None,
+            connect_under_reset: false,
+            allow_erase_all: false,
         }
     }
 
@@ -47,6 +82,9 @@ impl KnownBoard for MicrobitV2 {
         BoardSettings {
             arch: Some("cortex-m4".to_string()),
             start_address: 0x00040000,
+            // The bootloader takes the first 32 KiB of flash.
+            kernel_address: Some(0x00008000),
+            flash_geometry: Some(FlashGeometry::uniform(4096)),
         }
     }
 }
@@ -7,8 +7,13 @@ pub mod board_settings;
 pub(crate) mod bootloader_serial;
 pub mod command_impl;
 pub mod connection;
+pub mod credentials;
 mod errors;
+pub mod external_flash;
+pub mod flash_geometry;
+pub mod kernel;
 pub mod known_boards;
+pub(crate) mod read_cache;
 pub mod tabs;
 
 use async_trait::async_trait;
@@ -16,9 +21,13 @@ use probe_rs::probe::DebugProbeInfo;
 use tokio_serial::SerialPortInfo;
 
 use crate::attributes::app_attributes::AppAttributes;
+use crate::attributes::bootloader_info::BootloaderInfo;
 use crate::attributes::general_attributes::GeneralAttributes;
 use crate::board_settings::BoardSettings;
+use crate::credentials::keys::PublicKey;
+use crate::credentials::verify::AppVerification;
 use crate::errors::*;
+use crate::kernel::KernelImage;
 use crate::tabs::tab::Tab;
 
 pub fn list_debug_probes() -> Vec<DebugProbeInfo> {
@@ -64,3 +73,105 @@ pub trait CommandInstall {
 pub trait CommandEraseApps {
     async fn erase_apps(&mut self, settings: &BoardSettings) -> Result<(), TockloaderError>;
 }
+
+/// What the board does after a [CommandReset::reset].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResetMode {
+    /// Start the kernel.
+    #[default]
+    Run,
+    /// Stop before the kernel runs: the core is halted when using a probe,
+    /// and the board stays in the bootloader when using serial.
+    Halt,
+}
+
+#[async_trait]
+pub trait CommandReset {
+    /// Reset the board. With a probe this resets the core. Over serial,
+    /// [ResetMode::Run] asks the bootloader to exit and start the kernel,
+    /// while [ResetMode::Halt] resets the board into the bootloader by
+    /// toggling DTR and RTS.
+    async fn reset(&mut self, mode: ResetMode) -> Result<(), TockloaderError>;
+}
+
+#[async_trait]
+pub trait CommandVerify {
+    /// Check the credentials footers of `apps`, as returned by
+    /// [`CommandList::list`] or [`CommandInfo::info`], against the
+    /// applications stored on the board.
+    async fn verify_apps(
+        &mut self,
+        apps: &[AppAttributes],
+        keys: &[PublicKey],
+    ) -> Result<Vec<AppVerification>, TockloaderError>;
+}
+
+#[async_trait]
+pub trait CommandAttributes {
+    /// Read the value of the bootloader attribute `key`.
+    async fn get_attribute(&mut self, key: &str) -> Result<String, TockloaderError>;
+
+    /// Store `value` under `key`, replacing the attribute if it already
+    /// exists. Keys are limited to 8 bytes and values to 55 bytes.
+    async fn set_attribute(&mut self, key: &str, value: &str) -> Result<(), TockloaderError>;
+
+    /// Clear the slot holding the attribute `key`.
+    async fn remove_attribute(&mut self, key: &str) -> Result<(), TockloaderError>;
+}
+
+#[async_trait]
+pub trait CommandBootloaderInfo {
+    /// Query the bootloader for information about itself, such as its name
+    /// and version.
+    async fn bootloader_info(&mut self) -> Result<BootloaderInfo, TockloaderError>;
+
+    /// Query the bootloader for the unique ID of the board.
+    async fn board_id(&mut self) -> Result<Vec<u8>, TockloaderError>;
+}
+
+#[async_trait]
+pub trait CommandExternalFlash {
+    /// Read `length` bytes of external flash at `address`. The data is
+    /// checked against a CRC computed by the bootloader.
+    async fn read_external(
+        &mut self,
+        address: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, TockloaderError>;
+
+    /// Write `data` to external flash at `address`, which must be aligned to
+    /// a page. The last page is padded with 0xFF. The written pages are
+    /// checked against a CRC computed by the bootloader.
+    async fn write_external(&mut self, address: u64, data: &[u8]) -> Result<(), TockloaderError>;
+
+    /// Erase `length` bytes of external flash at `address`. Both must be
+    /// aligned to a page.
+    async fn erase_external(&mut self, address: u64, length: u64) -> Result<(), TockloaderError>;
+}
+
+#[async_trait]
+pub trait CommandFlashBootloader {
+    /// Write the bootloader `binary` at `address`, usually the start of flash.
+    /// The bootloader version is checked before and after flashing, and a
+    /// downgrade is refused unless `force` is set.
+    async fn flash_bootloader(
+        &mut self,
+        binary: &[u8],
+        address: u64,
+        force: bool,
+    ) -> Result<(), TockloaderError>;
+}
+
+#[async_trait]
+pub trait CommandFlashKernel {
+    /// Write `kernel` to the board, checking that it ends before the start of
+    /// applications. See [KernelImage::placement] for where it is written. A
+    /// kernel linked for another address than the board's is refused unless
+    /// `force` is set.
+    async fn flash_kernel(
+        &mut self,
+        settings: &BoardSettings,
+        kernel: &KernelImage,
+        force: bool,
+    ) -> Result<(), TockloaderError>;
+}
@@ -0,0 +1,166 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+
+use crate::bootloader_serial::{
+    bootloader_address, issue_command, BootloaderPort, Command, Response,
+};
+use crate::errors::{InternalError, TockloaderError};
+
+/// Size, and alignment, of the chunks fetched by a [ReadCache]. This is the
+/// largest power of two the bootloader returns for a single `ReadRange`.
+pub(crate) const READ_CHUNK_SIZE: usize = 2048;
+
+/// Something that can read a range of the board memory in a single request.
+#[async_trait]
+pub(crate) trait ReadRange {
+    async fn read_range(&mut self, address: u64, length: usize)
+        -> Result<Vec<u8>, TockloaderError>;
+}
+
+#[async_trait]
+impl ReadRange for BootloaderPort {
+    async fn read_range(
+        &mut self,
+        address: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, TockloaderError> {
+        let mut pkt = bootloader_address(address)?.to_le_bytes().to_vec();
+        pkt.extend((length as u16).to_le_bytes());
+
+        let (_, buf) = issue_command(
+            self,
+            Command::ReadRange,
+            pkt,
+            true,
+            length,
+            Response::ReadRange,
+        )
+        .await?;
+        Ok(buf)
+    }
+}
+
+/// Read cache for slow links. Reads are served from aligned chunks of
+/// [READ_CHUNK_SIZE] bytes, each fetched with a single request the first time
+/// it is needed. Walking the TBF headers and footers of applications then
+/// costs a request per chunk instead of several requests per application.
+///
+/// The cache does not see writes, so it should only live as long as the flash
+/// is not modified.
+pub(crate) struct ReadCache<'a, R: ?Sized> {
+    reader: &'a mut R,
+    chunk_size: usize,
+    chunks: BTreeMap<u64, Vec<u8>>,
+}
+
+impl<'a, R: ReadRange + Send + ?Sized> ReadCache<'a, R> {
+    pub(crate) fn new(reader: &'a mut R) -> Self {
+        Self::with_chunk_size(reader, READ_CHUNK_SIZE)
+    }
+
+    pub(crate) fn with_chunk_size(reader: &'a mut R, chunk_size: usize) -> Self {
+        ReadCache {
+            reader,
+            chunk_size,
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    /// Read `length` bytes at `address`, fetching the missing chunks.
+    pub(crate) async fn read(
+        &mut self,
+        address: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, TockloaderError> {
+        let chunk_size = self.chunk_size as u64;
+        let end = address + length as u64;
+
+        let mut result = Vec::with_capacity(length);
+        let mut chunk_start = address / chunk_size * chunk_size;
+        while chunk_start < end {
+            if !self.chunks.contains_key(&chunk_start) {
+                log::trace!("Reading chunk at {chunk_start:#x}");
+                let data = self.reader.read_range(chunk_start, self.chunk_size).await?;
+                self.chunks.insert(chunk_start, data);
+            }
+
+            let from = (address.max(chunk_start) - chunk_start) as usize;
+            let to = (end.min(chunk_start + chunk_size) - chunk_start) as usize;
+            let data = self.chunks[&chunk_start]
+                .get(from..to)
+                .ok_or(InternalError::ShortRead {
+                    address: chunk_start,
+                    length: self.chunk_size,
+                })?;
+            result.extend_from_slice(data);
+
+            chunk_start += chunk_size;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory flash that records every request.
+    struct FakeFlash {
+        data: Vec<u8>,
+        requests: Vec<(u64, usize)>,
+    }
+
+    #[async_trait]
+    impl ReadRange for FakeFlash {
+        async fn read_range(
+            &mut self,
+            address: u64,
+            length: usize,
+        ) -> Result<Vec<u8>, TockloaderError> {
+            self.requests.push((address, length));
+            let start = (address as usize).min(self.data.len());
+            let end = (start + length).min(self.data.len());
+            Ok(self.data[start..end].to_vec())
+        }
+    }
+
+    fn flash() -> FakeFlash {
+        FakeFlash {
+            data: (0..8192).map(|i| i as u8).collect(),
+            requests: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn aligned_chunks() {
+        let mut flash = flash();
+        let mut cache = ReadCache::with_chunk_size(&mut flash, 1024);
+
+        // Spans two chunks.
+        let data = cache.read(1000, 100).await.unwrap();
+        assert_eq!(data, flash_bytes(1000, 100));
+        // Already cached.
+        assert_eq!(cache.read(1020, 8).await.unwrap(), flash_bytes(1020, 8));
+        assert_eq!(cache.read(4096, 0).await.unwrap(), Vec::<u8>::new());
+
+        assert_eq!(flash.requests, vec![(0, 1024), (1024, 1024)]);
+    }
+
+    #[tokio::test]
+    async fn short_read() {
+        let mut flash = flash();
+        flash.data.truncate(4000);
+        let mut cache = ReadCache::with_chunk_size(&mut flash, 1024);
+
+        assert!(cache.read(3990, 20).await.is_err());
+    }
+
+    fn flash_bytes(address: usize, length: usize) -> Vec<u8> {
+        (address..address + length).map(|i| i as u8).collect()
+    }
+}
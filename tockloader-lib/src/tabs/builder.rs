@@ -0,0 +1,299 @@
+// Licensed under the Apache License, Version 2.0 or the MIT License.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Copyright OXIDOS AUTOMOTIVE 2024.
+
+use std::fs::File;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tar::{Builder, Header};
+use toml::value::{Date, Datetime, Offset, Time};
+
+use crate::errors::{TabError, TockloaderError};
+use crate::tabs::metadata::{Metadata, TockKernelVersion};
+use crate::tabs::tab::{Tab, TbfFile};
+
+/// Version of the TAB format written by [`TabBuilder`].
+const TAB_VERSION: i64 = 1;
+
+/// Creates new TAB files, or edits existing ones.
+///
+/// The archive layout matches the one produced by elf2tab: a `metadata.toml`
+/// file followed by one `<arch>.tbf` file per architecture, all at the root of
+/// the archive. Anything written by this builder can be read back using
+/// [`Tab::open`].
+///
+/// ```no_run
+/// # use tockloader_lib::tabs::builder::TabBuilder;
+/// # fn example(tbf: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+/// TabBuilder::new("blink")
+///     .minimum_kernel_version(2, 1)
+///     .only_for_boards(vec!["microbit_v2".to_owned()])
+///     .add_tbf("cortex-m4", tbf)?
+///     .write_to_file("blink.tab")?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TabBuilder {
+    metadata: Metadata,
+    tbf_files: Vec<TbfFile>,
+}
+
+impl TabBuilder {
+    /// Start a new, empty TAB for the application `name`. The build date is
+    /// set to the current time and the minimum kernel version to 2.0.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            metadata: Metadata {
+                tab_version: TAB_VERSION,
+                name: name.into(),
+                minimum_tock_kernel_version: TockKernelVersion { major: 2, minor: 0 },
+                build_date: now(),
+                only_for_boards: None,
+                extra: toml::Table::new(),
+            },
+            tbf_files: Vec::new(),
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.metadata.name = name.into();
+        self
+    }
+
+    pub fn minimum_kernel_version(mut self, major: u32, minor: u32) -> Self {
+        self.metadata.minimum_tock_kernel_version = TockKernelVersion { major, minor };
+        self
+    }
+
+    /// Restrict the TAB to the given boards. An empty list means the TAB can
+    /// be installed on any board.
+    pub fn only_for_boards(mut self, boards: Vec<String>) -> Self {
+        self.metadata.only_for_boards = if boards.is_empty() {
+            None
+        } else {
+            Some(boards)
+        };
+        self
+    }
+
+    pub fn build_date(mut self, build_date: Datetime) -> Self {
+        self.metadata.build_date = build_date;
+        self
+    }
+
+    /// Add the TBF binary for `arch`, replacing any binary previously stored
+    /// under `<arch>.tbf`.
+    ///
+    /// # Returns
+    /// - Err(TabError::InvalidTbf) or Err(TabError::Parsing): if `tbf` does not
+    ///   start with a valid TBF header
+    pub fn add_tbf(mut self, arch: &str, tbf: Vec<u8>) -> Result<Self, TockloaderError> {
+        let file = TbfFile {
+            filename: format!("{arch}.tbf"),
+            data: tbf,
+        };
+        file.parse_header()?;
+
+        self.tbf_files.retain(|f| f.filename != file.filename);
+        self.tbf_files.push(file);
+        Ok(self)
+    }
+
+    /// Remove every binary built for `arch`, including the ones compiled for
+    /// fixed addresses.
+    pub fn remove_arch(mut self, arch: &str) -> Self {
+        self.tbf_files.retain(|f| f.arch() != arch);
+        self
+    }
+
+    /// Build the TAB in memory, without writing it anywhere.
+    pub fn build(self) -> Tab {
+        Tab {
+            metadata: self.metadata,
+            tbf_files: self.tbf_files,
+        }
+    }
+
+    /// Write the TAB archive into `writer`.
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), TockloaderError> {
+        let mut archive = Builder::new(writer);
+
+        let metadata = self.metadata.to_toml()?;
+        append_file(&mut archive, "metadata.toml", metadata.as_bytes())?;
+
+        for file in &self.tbf_files {
+            append_file(&mut archive, &file.filename, &file.data)?;
+        }
+
+        archive.into_inner().map_err(TabError::IO)?;
+        Ok(())
+    }
+
+    /// Write the TAB archive into a new file at `path`, overwriting it if it
+    /// already exists.
+    pub fn write_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), TockloaderError> {
+        let file = File::create(path).map_err(TabError::IO)?;
+        self.write(file)
+    }
+}
+
+/// Edit an existing TAB. The metadata, including keys unknown to tockloader,
+/// and all binaries are kept as they are.
+impl From<Tab> for TabBuilder {
+    fn from(tab: Tab) -> Self {
+        Self {
+            metadata: tab.metadata,
+            tbf_files: tab.tbf_files,
+        }
+    }
+}
+
+fn append_file<W: Write>(
+    archive: &mut Builder<W>,
+    path: &str,
+    data: &[u8],
+) -> Result<(), TockloaderError> {
+    let mut header = Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    // Keep the archive reproducible; the build date lives in metadata.toml.
+    header.set_mtime(0);
+    header.set_cksum();
+
+    archive
+        .append_data(&mut header, path, data)
+        .map_err(|e| TabError::IO(e).into())
+}
+
+/// Current UTC time as a TOML datetime.
+fn now() -> Datetime {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    // Convert days since the epoch into a calendar date. See
+    // <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let time_of_day = seconds % 86400;
+
+    Datetime {
+        date: Some(Date {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        }),
+        time: Some(Time {
+            hour: (time_of_day / 3600) as u8,
+            minute: (time_of_day % 3600 / 60) as u8,
+            second: (time_of_day % 60) as u8,
+            nanosecond: 0,
+        }),
+        offset: Some(Offset::Z),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE_TBF: &[u8] = include_bytes!("../../../tbf-parser/tests/flashes/simple.dat");
+    const RSA_TBF: &[u8] = include_bytes!("../../../tbf-parser/tests/flashes/footerRSA4096.dat");
+
+    fn temp_tab(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("tockloader-{}-{name}.tab", std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_tab("round-trip");
+        let build_date: Datetime = "2024-05-13T10:21:44Z".parse().unwrap();
+
+        TabBuilder::new("heart")
+            .minimum_kernel_version(2, 1)
+            .only_for_boards(vec!["microbit_v2".to_owned(), "nrf52840dk".to_owned()])
+            .build_date(build_date)
+            .add_tbf("cortex-m4", SIMPLE_TBF.to_vec())
+            .unwrap()
+            .add_tbf("cortex-m0", RSA_TBF.to_vec())
+            .unwrap()
+            .write_to_file(&path)
+            .unwrap();
+
+        let tab = Tab::open(path.clone()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(tab.name(), "heart");
+        assert_eq!(tab.metadata().tab_version, TAB_VERSION);
+        assert_eq!(tab.build_date(), &build_date);
+        assert_eq!(
+            tab.minimum_kernel_version(),
+            TockKernelVersion { major: 2, minor: 1 }
+        );
+        assert_eq!(
+            tab.only_for_boards(),
+            Some(&["microbit_v2".to_owned(), "nrf52840dk".to_owned()][..])
+        );
+        assert_eq!(tab.architectures(), vec!["cortex-m4", "cortex-m0"]);
+        assert_eq!(tab.extract_binary("cortex-m4").unwrap(), SIMPLE_TBF);
+        assert_eq!(tab.extract_binary("cortex-m0").unwrap(), RSA_TBF);
+    }
+
+    #[test]
+    fn edit_existing_tab() {
+        let path = temp_tab("edit");
+
+        TabBuilder::new("heart")
+            .add_tbf("cortex-m4", SIMPLE_TBF.to_vec())
+            .unwrap()
+            .add_tbf("cortex-m0", SIMPLE_TBF.to_vec())
+            .unwrap()
+            .write_to_file(&path)
+            .unwrap();
+
+        let tab = Tab::open(path.clone()).unwrap();
+        assert!(tab.is_compatible_with_board(&"any_board".to_owned()));
+
+        TabBuilder::from(tab)
+            .remove_arch("cortex-m0")
+            .add_tbf("cortex-m4", RSA_TBF.to_vec())
+            .unwrap()
+            .add_tbf("rv32imc", SIMPLE_TBF.to_vec())
+            .unwrap()
+            .write_to_file(&path)
+            .unwrap();
+
+        let tab = Tab::open(path.clone()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(tab.name(), "heart");
+        assert_eq!(tab.architectures(), vec!["cortex-m4", "rv32imc"]);
+        assert_eq!(tab.extract_binary("cortex-m4").unwrap(), RSA_TBF);
+        assert!(tab.extract_binary("cortex-m0").is_err());
+    }
+
+    #[test]
+    fn build_date_is_valid() {
+        let date = now();
+        let reparsed: Datetime = date.to_string().parse().unwrap();
+        assert_eq!(date, reparsed);
+        assert!(date.date.unwrap().year >= 2024);
+    }
+}
@@ -2,12 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
+use std::fmt;
+
 use crate::errors::{TabError, TockloaderError};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[allow(dead_code)]
-#[derive(Deserialize)]
-pub(super) struct Metadata {
+/// Contents of the `metadata.toml` file found inside every TAB.
+///
+/// Keys that tockloader does not know about are kept in
+/// [`extra`](Metadata::extra), so that no information is lost when inspecting
+/// a TAB.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Metadata {
     #[serde(rename = "tab-version")]
     pub tab_version: i64,
     pub name: String,
@@ -18,15 +24,24 @@ pub(super) struct Metadata {
     #[serde(
         default,
         deserialize_with = "deserialize_boards",
+        serialize_with = "serialize_boards",
         rename = "only-for-boards"
     )]
     pub only_for_boards: Option<Vec<String>>,
+    /// Any other key present in `metadata.toml`.
+    #[serde(flatten)]
+    pub extra: toml::Table,
 }
 
 impl Metadata {
     pub fn new(metadata: String) -> Result<Self, TockloaderError> {
         toml::from_str(&metadata).map_err(|e| TabError::InvalidMetadata(e).into())
     }
+
+    /// Render the metadata back into the `metadata.toml` format.
+    pub fn to_toml(&self) -> Result<String, TockloaderError> {
+        toml::to_string(self).map_err(|e| TabError::MetadataSerialization(e).into())
+    }
 }
 
 fn deserialize_boards<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
@@ -34,16 +49,52 @@ where
     D: serde::Deserializer<'de>,
 {
     let opt = Option::<String>::deserialize(deserializer)?;
-    Ok(opt.map(|s| s.split(',').map(|s| s.trim().to_string()).collect()))
+
+    // elf2tab writes `only-for-boards = ""` when the app is not restricted to
+    // any board, which must not be read as "compatible with no board".
+    Ok(opt
+        .map(|s| {
+            s.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|boards| !boards.is_empty()))
+}
+
+fn serialize_boards<S>(boards: &Option<Vec<String>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    // Mirror elf2tab, which always writes the key and leaves it empty for apps
+    // that are not restricted to any board.
+    boards
+        .as_ref()
+        .map_or(String::new(), |boards| boards.join(","))
+        .serialize(serializer)
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub(super) struct TockKernelVersion {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TockKernelVersion {
     pub major: u32,
     pub minor: u32,
 }
 
+impl fmt::Display for TockKernelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl Serialize for TockKernelVersion {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
 impl<'de> Deserialize<'de> for TockKernelVersion {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -67,3 +118,58 @@ impl<'de> Deserialize<'de> for TockKernelVersion {
         Ok(TockKernelVersion { major, minor })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_elf2tab_metadata() {
+        let metadata = Metadata::new(
+            r#"
+            tab-version = 1
+            name = "blink"
+            only-for-boards = ""
+            build-date = 2024-05-13T10:21:44Z
+            minimum-tock-kernel-version = "2.1"
+            "#
+            .to_owned(),
+        )
+        .unwrap();
+
+        assert_eq!(metadata.tab_version, 1);
+        assert_eq!(metadata.name, "blink");
+        assert_eq!(metadata.only_for_boards, None);
+        assert_eq!(metadata.build_date.to_string(), "2024-05-13T10:21:44Z");
+        assert_eq!(
+            metadata.minimum_tock_kernel_version,
+            TockKernelVersion { major: 2, minor: 1 }
+        );
+        assert!(metadata.extra.is_empty());
+    }
+
+    #[test]
+    fn keeps_unknown_keys() {
+        let metadata = Metadata::new(
+            r#"
+            tab-version = 1
+            name = "blink"
+            only-for-boards = "microbit_v2, nrf52840dk"
+            build-date = 2024-05-13T10:21:44Z
+            minimum-tock-kernel-version = "2.1"
+            app-version = 3
+            "#
+            .to_owned(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            metadata.only_for_boards,
+            Some(vec!["microbit_v2".to_owned(), "nrf52840dk".to_owned()])
+        );
+        assert_eq!(
+            metadata.extra.get("app-version"),
+            Some(&toml::Value::Integer(3))
+        );
+    }
+}
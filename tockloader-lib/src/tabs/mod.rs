@@ -2,5 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
-mod metadata;
+pub mod builder;
+pub mod metadata;
 pub mod tab;
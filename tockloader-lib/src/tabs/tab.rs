@@ -2,28 +2,86 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 // Copyright OXIDOS AUTOMOTIVE 2024.
 
+use crate::credentials::keys::PrivateKey;
+use crate::credentials::sign::sign_tbf;
 use crate::errors::{TabError, TockloaderError};
-use crate::tabs::metadata::Metadata;
+use crate::tabs::builder::TabBuilder;
+use crate::tabs::metadata::{Metadata, TockKernelVersion};
 use std::fs::File;
 use std::io::Read;
 use tar::Archive;
+use tbf_parser::parse::{parse_tbf_header, parse_tbf_header_lengths};
+use tbf_parser::types::TbfHeader;
 
-struct TbfFile {
-    pub filename: String,
-    pub data: Vec<u8>,
+/// A single TBF binary stored inside a TAB.
+pub struct TbfFile {
+    pub(super) filename: String,
+    pub(super) data: Vec<u8>,
+}
+
+impl TbfFile {
+    /// Name of the file inside the TAB archive (e.g. `cortex-m4.tbf`).
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Architecture this binary was compiled for.
+    ///
+    /// elf2tab names TBFs either `<arch>.tbf` or, for apps with fixed
+    /// addresses, `<arch>.<flash>.<ram>.tbf`. In both cases the architecture
+    /// is the part before the first dot.
+    pub fn arch(&self) -> &str {
+        self.filename
+            .split_once('.')
+            .map_or(self.filename.as_str(), |(arch, _)| arch)
+    }
+
+    /// Raw contents of the TBF, including header and footers.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Parse the TBF header of this binary.
+    pub fn parse_header(&self) -> Result<TbfHeader, TockloaderError> {
+        let lengths: &[u8; 8] = self
+            .data
+            .get(0..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(TabError::InvalidTbf(self.filename.clone()))?;
+
+        let (version, header_size, _total_size) = parse_tbf_header_lengths(lengths)
+            .map_err(|_| TabError::InvalidTbf(self.filename.clone()))?;
+
+        let header_data = self
+            .data
+            .get(0..header_size as usize)
+            .ok_or(TabError::InvalidTbf(self.filename.clone()))?;
+
+        parse_tbf_header(header_data, version).map_err(|e| TabError::Parsing(e).into())
+    }
 }
 
 pub struct Tab {
-    metadata: Metadata,
-    tbf_files: Vec<TbfFile>,
+    pub(super) metadata: Metadata,
+    pub(super) tbf_files: Vec<TbfFile>,
 }
 
 impl Tab {
     pub fn open(path: String) -> Result<Self, TockloaderError> {
+        let tab_file = File::open(path).map_err(TabError::IO)?;
+        Self::from_reader(tab_file)
+    }
+
+    /// Read a TAB from an in-memory buffer, such as a file dropped into a UI.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TockloaderError> {
+        Self::from_reader(bytes)
+    }
+
+    /// Read a TAB archive from any byte stream, such as stdin.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, TockloaderError> {
         let mut metadata = None;
         let mut tbf_files = Vec::new();
-        let tab_file = File::open(path).map_err(TabError::IO)?;
-        let mut archive = Archive::new(tab_file);
+        let mut archive = Archive::new(reader);
 
         for archive_entry in archive.entries().map_err(TabError::IO)? {
             let mut archive_file = archive_entry.map_err(TabError::IO)?;
@@ -60,6 +118,80 @@ impl Tab {
         }
     }
 
+    /// Wrap a bare TBF, without a TAB around it, built for `arch`.
+    ///
+    /// TBF headers do not record the architecture, so it must be provided by
+    /// the caller. The metadata is synthesized from the TBF header: the name
+    /// is the package name, the minimum kernel version comes from the kernel
+    /// version TLV (2.0 if missing) and the build date is the current time.
+    pub fn from_tbf(arch: &str, tbf: Vec<u8>) -> Result<Self, TockloaderError> {
+        let file = TbfFile {
+            filename: format!("{arch}.tbf"),
+            data: tbf,
+        };
+        let header = file.parse_header()?;
+
+        let name = header.get_package_name().unwrap_or_default();
+        let (major, minor) = header.get_kernel_version().unwrap_or((2, 0));
+
+        Ok(TabBuilder::new(name)
+            .minimum_kernel_version(major.into(), minor.into())
+            .add_tbf(arch, file.data)?
+            .build())
+    }
+
+    /// Full contents of `metadata.toml`.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Name of the application packaged in this TAB.
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    /// Date at which the TAB was created.
+    pub fn build_date(&self) -> &toml::value::Datetime {
+        &self.metadata.build_date
+    }
+
+    /// Oldest kernel version the application can run on.
+    pub fn minimum_kernel_version(&self) -> TockKernelVersion {
+        self.metadata.minimum_tock_kernel_version
+    }
+
+    /// Boards this TAB is restricted to, or `None` if it can be installed on
+    /// any board.
+    pub fn only_for_boards(&self) -> Option<&[String]> {
+        self.metadata.only_for_boards.as_deref()
+    }
+
+    /// All TBF binaries found in the TAB.
+    pub fn tbf_files(&self) -> &[TbfFile] {
+        &self.tbf_files
+    }
+
+    /// Architectures for which this TAB contains a binary, in archive order
+    /// and without duplicates.
+    pub fn architectures(&self) -> Vec<&str> {
+        let mut archs: Vec<&str> = Vec::new();
+        for file in &self.tbf_files {
+            if !archs.contains(&file.arch()) {
+                archs.push(file.arch());
+            }
+        }
+        archs
+    }
+
+    /// Add a credentials footer signed with `key` to every TBF in the TAB.
+    /// See [`sign_tbf`].
+    pub fn sign(&mut self, key: &PrivateKey) -> Result<(), TockloaderError> {
+        for file in &mut self.tbf_files {
+            file.data = sign_tbf(&file.data, key)?;
+        }
+        Ok(())
+    }
+
     pub fn is_compatible_with_kernel_verison(&self, _kernel_version: u32) -> bool {
         // Kernel version seems to not be working properly on the microbit bootloader. It is always
         // "1" despite the actual version.
@@ -85,3 +217,49 @@ impl Tab {
         Err(TabError::MissingBinary(arch.to_owned()).into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE_TBF: &[u8] = include_bytes!("../../../tbf-parser/tests/flashes/simple.dat");
+
+    #[test]
+    fn read_from_bytes() {
+        let mut buffer = Vec::new();
+        TabBuilder::new("heart")
+            .add_tbf("cortex-m4", SIMPLE_TBF.to_vec())
+            .unwrap()
+            .write(&mut buffer)
+            .unwrap();
+
+        let tab = Tab::from_bytes(&buffer).unwrap();
+        assert_eq!(tab.name(), "heart");
+        assert_eq!(tab.extract_binary("cortex-m4").unwrap(), SIMPLE_TBF);
+
+        assert!(Tab::from_bytes(SIMPLE_TBF).is_err());
+    }
+
+    #[test]
+    fn bare_tbf() {
+        let tab = Tab::from_tbf("cortex-m4", SIMPLE_TBF.to_vec()).unwrap();
+
+        assert_eq!(tab.name(), "_heart");
+        assert_eq!(
+            tab.minimum_kernel_version(),
+            TockKernelVersion { major: 2, minor: 0 }
+        );
+        assert_eq!(tab.only_for_boards(), None);
+        assert_eq!(tab.architectures(), vec!["cortex-m4"]);
+        assert_eq!(tab.extract_binary("cortex-m4").unwrap(), SIMPLE_TBF);
+
+        assert!(Tab::from_tbf("cortex-m4", vec![0xFF; 64]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_tbf() {
+        assert!(TabBuilder::new("broken")
+            .add_tbf("cortex-m4", vec![0xFF; 64])
+            .is_err());
+    }
+}